@@ -31,19 +31,19 @@ fn main() -> Result<(), Mpu6886Error<LinuxI2CError>> {
 
     // test sleep. Default no, in wake()
     println!("Test sleep");
-    assert_eq!(mpu.get_sleep_enabled()?, false);
+    assert!(!mpu.get_sleep_enabled()?);
     mpu.set_sleep_enabled(true)?;
-    assert_eq!(mpu.get_sleep_enabled()?, true);
+    assert!(mpu.get_sleep_enabled()?);
     mpu.set_sleep_enabled(false)?;
-    assert_eq!(mpu.get_sleep_enabled()?, false);
+    assert!(!mpu.get_sleep_enabled()?);
 
     // test temp enable/disable
     println!("Test temp enable/disable");
     mpu.set_temp_enabled(false)?;
-    assert_eq!(mpu.get_temp_enabled()?, false);
+    assert!(!mpu.get_temp_enabled()?);
     assert_eq!(mpu.get_temp()?, 36.53);
     mpu.set_temp_enabled(true)?;
-    assert_eq!(mpu.get_temp_enabled()?, true);
+    assert!(mpu.get_temp_enabled()?);
     assert_ne!(mpu.get_temp()?, 36.53);
 
     // Test clksel: GXAXIS per default, set in wake()
@@ -69,8 +69,8 @@ fn main() -> Result<(), Mpu6886Error<LinuxI2CError>> {
     mpu.reset_device(&mut delay)?;
     assert_eq!(mpu.get_accel_range()?, AccelRange::G2);
     assert_eq!(mpu.get_gyro_range()?, GyroRange::D250);
-    assert_eq!(mpu.get_sleep_enabled()?, true);
-    assert_eq!(mpu.get_temp_enabled()?, true);
+    assert!(mpu.get_sleep_enabled()?);
+    assert!(mpu.get_temp_enabled()?);
 
     println!("Test successful");
     Ok(())
@@ -0,0 +1,617 @@
+//! Async mpu6886 driver for `embedded-hal-async` (e.g. Embassy), enabled by the feature `async`
+//!
+//! [`Mpu6886Async`] is an I2C only subset of the blocking [`Mpu6886`](crate::Mpu6886):
+//! configuration, scaled readings, the interrupt status and the FIFO, including the count driven
+//! `drain_fifo`, which shares its decisions with the blocking driver. Every bus access and every
+//! delay is awaited, so the executor is free while waiting for the sensor. Calibration, low power,
+//! interrupt configuration, self-test and sample rate configuration are only in the blocking driver.
+//!
+//! ```ignore
+//! let mut mpu = Mpu6886Async::new(i2c);
+//! mpu.init(&mut Delay).await?;
+//! let acc = mpu.get_acc().await?;
+//! ```
+
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+use nalgebra::Vector3;
+#[cfg(feature = "float")]
+use nalgebra::Vector2;
+
+use crate::bits;
+use crate::config::*;
+use crate::device::*;
+use crate::error::*;
+use crate::fifo::{self, FifoLayout, FifoMode, FifoState};
+use crate::raw::{self, RawSample};
+use crate::{check_chip_id, STARTUP_DELAY_MS, WAKE_PWR_MGMT_1};
+#[cfg(feature = "float")]
+use crate::fifo::{DrainPlan, FifoDrain, Sample, FIFO_READ_CHUNK};
+#[cfg(feature = "float")]
+use crate::{acc_angles, fifo_data, fifo_data_si};
+
+/// Handles all operations on/with mpu6886, async version
+pub struct Mpu6886Async<I> {
+    i2c: I,
+    slave_addr: u8,
+    // sensitivities and FIFO drain state are only read by the scaled readings
+    #[cfg_attr(not(feature = "float"), allow(dead_code))]
+    acc_sensitivity: f32,
+    #[cfg_attr(not(feature = "float"), allow(dead_code))]
+    gyro_sensitivity: f32,
+    /// FIFO layout and drain state
+    fifo: FifoState,
+}
+
+impl<I> Mpu6886Async<I> {
+    /// Side effect free constructor with default sensitivies, no calibration
+    pub fn new(i2c: I) -> Self {
+        Self::new_with_addr(i2c, DEFAULT_SLAVE_ADDR)
+    }
+
+    /// custom sensitivity
+    pub fn new_with_sens(i2c: I, arange: AccelRange, grange: GyroRange) -> Self {
+        Self::new_with_addr_and_sens(i2c, DEFAULT_SLAVE_ADDR, arange, grange)
+    }
+
+    /// Same as `new`, but the chip address can be specified (e.g. 0x69, if the A0 pin is pulled up)
+    pub fn new_with_addr(i2c: I, slave_addr: u8) -> Self {
+        Self::new_with_addr_and_sens(i2c, slave_addr, AccelRange::G2, GyroRange::D250)
+    }
+
+    /// Combination of `new_with_sens` and `new_with_addr`
+    pub fn new_with_addr_and_sens(i2c: I, slave_addr: u8, arange: AccelRange, grange: GyroRange) -> Self {
+        Mpu6886Async {
+            i2c,
+            slave_addr,
+            acc_sensitivity: arange.sensitivity(),
+            gyro_sensitivity: grange.sensitivity(),
+            fifo: FifoState::default(),
+        }
+    }
+
+    /// Returns the i2c bus
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+impl<I, E> Mpu6886Async<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Wakes mpu6886 with all sensors enabled (default)
+    async fn wake<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+        // mpu6886 has sleep enabled by default -> set bit 0 to wake
+        // Set clock source to be PLL with x-axis gyroscope reference, bits 2:0 = 001 (See Register Map )
+        self.write_byte(PWR_MGMT_1::ADDR, WAKE_PWR_MGMT_1).await?;
+        delay.delay_ms(STARTUP_DELAY_MS.into()).await;
+        Ok(())
+    }
+
+    /// Init wakes mpu6886 and verifies register addr, e.g. in i2c
+    pub async fn init<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+        self.wake(delay).await?;
+        self.verify().await?;
+        self.set_accel_range(AccelRange::G2).await?;
+        self.set_gyro_range(GyroRange::D250).await?;
+        Ok(())
+    }
+
+    /// Verifies device to address 0x68 with WHOAMI.addr() Register
+    async fn verify(&mut self) -> Result<(), Mpu6886Error<E>> {
+        let chip_type = self.read_byte(WHOAMI).await?;
+        check_chip_id(chip_type)
+    }
+
+    /// reset device
+    pub async fn reset_device<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::DEVICE_RESET, true).await?;
+        delay.delay_ms(STARTUP_DELAY_MS.into()).await;
+        self.fifo.device_reset();
+        // Note: Reset sets sleep to true! Section register map: resets PWR_MGMT to 0x40
+        Ok(())
+    }
+
+    /// set clock source, see `Mpu6886::set_clock_source`
+    pub async fn set_clock_source(&mut self, source: CLKSEL) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::CLKSEL.bit, PWR_MGMT_1::CLKSEL.length, source as u8).await
+    }
+
+    /// get current clock source
+    pub async fn get_clock_source(&mut self) -> Result<CLKSEL, Mpu6886Error<E>> {
+        let source = self.read_bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::CLKSEL.bit, PWR_MGMT_1::CLKSEL.length).await?;
+        Ok(CLKSEL::from(source))
+    }
+
+    /// enable, disable sleep of sensor
+    pub async fn set_sleep_enabled(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, enable).await
+    }
+
+    /// get sleep status
+    pub async fn get_sleep_enabled(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        Ok(self.read_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP).await? != 0)
+    }
+
+    /// enable, disable temperature measurement of sensor
+    pub async fn set_temp_enabled(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS, !enable).await
+    }
+
+    /// get temperature sensor status
+    pub async fn get_temp_enabled(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        Ok(self.read_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS).await? == 0)
+    }
+
+    /// Set gyro range, and update sensitivity accordingly
+    pub async fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(GYRO_CONFIG::ADDR,
+                        GYRO_CONFIG::FS_SEL.bit,
+                        GYRO_CONFIG::FS_SEL.length,
+                        range as u8).await?;
+
+        self.gyro_sensitivity = range.sensitivity();
+        Ok(())
+    }
+
+    /// get current gyro range
+    pub async fn get_gyro_range(&mut self) -> Result<GyroRange, Mpu6886Error<E>> {
+        let byte = self.read_bits(GYRO_CONFIG::ADDR,
+                                  GYRO_CONFIG::FS_SEL.bit,
+                                  GYRO_CONFIG::FS_SEL.length).await?;
+
+        Ok(GyroRange::from(byte))
+    }
+
+    /// set accel range, and update sensitivy accordingly
+    pub async fn set_accel_range(&mut self, range: AccelRange) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(ACCEL_CONFIG::ADDR,
+                        ACCEL_CONFIG::FS_SEL.bit,
+                        ACCEL_CONFIG::FS_SEL.length,
+                        range as u8).await?;
+
+        self.acc_sensitivity = range.sensitivity();
+        Ok(())
+    }
+
+    /// get current accel_range
+    pub async fn get_accel_range(&mut self) -> Result<AccelRange, Mpu6886Error<E>> {
+        let byte = self.read_bits(ACCEL_CONFIG::ADDR,
+                                  ACCEL_CONFIG::FS_SEL.bit,
+                                  ACCEL_CONFIG::FS_SEL.length).await?;
+
+        Ok(AccelRange::from(byte))
+    }
+
+    /// get accel filter bandwidth (`A_DLPF_CFG` and `ACCEL_FCHOICE_B`)
+    pub async fn get_accel_bandwith(&mut self) -> Result<AccelBw, Mpu6886Error<E>> {
+        let bw_sel = self.read_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_BW.bit, ACCEL_CONFIG_2::ACCEL_BW.length).await?;
+        Ok(AccelBw::try_from(bw_sel)?)
+    }
+
+    /// set accel filter bandwidth (`A_DLPF_CFG` and `ACCEL_FCHOICE_B`)
+    pub async fn set_accel_bw(&mut self, bw: AccelBw) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_BW.bit, ACCEL_CONFIG_2::ACCEL_BW.length, bw.bits()).await
+    }
+
+    /// get accel averaging filter of low power mode (`DEC2_CFG`)
+    pub async fn get_accel_averaging(&mut self) -> Result<AccelAveraging, Mpu6886Error<E>> {
+        let avg = self.read_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::DEC2_CFG.bit, ACCEL_CONFIG_2::DEC2_CFG.length).await?;
+        Ok(AccelAveraging::from(avg))
+    }
+
+    /// set accel averaging filter of low power mode (`DEC2_CFG`)
+    pub async fn set_accel_averaging(&mut self, avg: AccelAveraging) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::DEC2_CFG.bit, ACCEL_CONFIG_2::DEC2_CFG.length, avg as u8).await
+    }
+
+    /// get gyro filter bandwidth (`DLPF_CFG` and `FCHOICE_B`)
+    pub async fn get_gyro_bandwith(&mut self) -> Result<GyroBw, Mpu6886Error<E>> {
+        let dlpf_cfg = self.read_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length).await?;
+        let fchoice_b = self.read_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length).await?;
+        Ok(GyroBw::from_fields(dlpf_cfg, fchoice_b)?)
+    }
+
+    /// set gyro filter bandwidth (`DLPF_CFG` and `FCHOICE_B`)
+    pub async fn set_gyro_bw(&mut self, bw: GyroBw) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length, bw.dlpf_cfg()).await?;
+        self.write_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length, bw.fchoice_b()).await
+    }
+
+    #[cfg(feature = "float")]
+    /// Roll and pitch estimation from raw accelerometer readings
+    /// NOTE: no yaw! no magnetometer present on mpu6886
+    pub async fn get_acc_angles(&mut self) -> Result<Vector2<f32>, Mpu6886Error<E>> {
+        let acc = self.get_acc().await?;
+        Ok(acc_angles(&acc))
+    }
+
+    /// Reads raw counts of the x, y, z registers starting at reg
+    async fn read_rot_raw(&mut self, reg: u8) -> Result<Vector3<i16>, Mpu6886Error<E>> {
+        let mut buf: [u8; 6] = [0; 6];
+        self.read_bytes(reg, &mut buf).await?;
+        Ok(raw::vector(&buf))
+    }
+
+    /// Accel, temp and gyro counts in one 14 byte burst from ACCEL_XOUT_H, see `RawSample`
+    pub async fn get_raw(&mut self) -> Result<RawSample, Mpu6886Error<E>> {
+        let mut buf = [0; raw::RAW_SAMPLE_SIZE];
+        self.read_bytes(ACC_REGX_H, &mut buf).await?;
+        Ok(RawSample::from_bytes(&buf))
+    }
+
+    /// Accelerometer counts
+    pub async fn get_acc_raw(&mut self) -> Result<Vector3<i16>, Mpu6886Error<E>> {
+        self.read_rot_raw(ACC_REGX_H).await
+    }
+
+    /// Gyro counts
+    pub async fn get_gyro_raw(&mut self) -> Result<Vector3<i16>, Mpu6886Error<E>> {
+        self.read_rot_raw(GYRO_REGX_H).await
+    }
+
+    /// Temperature counts
+    pub async fn get_temp_raw(&mut self) -> Result<i16, Mpu6886Error<E>> {
+        let mut buf: [u8; 2] = [0; 2];
+        self.read_bytes(TEMP_OUT_H, &mut buf).await?;
+        Ok(raw::temp(&buf))
+    }
+
+    #[cfg(feature = "float")]
+    /// Accelerometer readings in g
+    pub async fn get_acc(&mut self) -> Result<Vector3<f32>, Mpu6886Error<E>> {
+        Ok(raw::accel_g(self.get_acc_raw().await?, self.acc_sensitivity))
+    }
+
+    #[cfg(feature = "float")]
+    /// Gyro readings in rad/s
+    pub async fn get_gyro(&mut self) -> Result<Vector3<f32>, Mpu6886Error<E>> {
+        Ok(raw::gyro_rad_s(self.get_gyro_raw().await?, self.gyro_sensitivity))
+    }
+
+    #[cfg(feature = "float")]
+    /// Sensor Temp in degrees celcius
+    pub async fn get_temp(&mut self) -> Result<f32, Mpu6886Error<E>> {
+        Ok(raw::temp_deg_c(self.get_temp_raw().await?))
+    }
+
+    /// enable writing data to the fifo output, see `Mpu6886::enable_fifo`
+    pub async fn enable_fifo(&mut self, accel: bool, gyro: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(FIFO_EN, fifo::ACCEL_FIFO_EN, accel).await?;
+        self.write_bit(FIFO_EN, fifo::GYRO_FIFO_EN, gyro).await?;
+        self.fifo.layout = FifoLayout::new(accel, gyro);
+        self.write_bit(USER_CTRL, fifo::SIG_COND_RST, true).await?;  // reset signal path
+        self.reset_fifo().await?; // reset fifo path
+        self.write_bit(USER_CTRL, fifo::FIFO_ENABLE, true).await?; // enable fifo
+        Ok(())
+    }
+
+    /// get packet layout of the FIFO from FIFO_EN, see `Mpu6886::get_fifo_layout`
+    pub async fn get_fifo_layout(&mut self) -> Result<Option<FifoLayout>, Mpu6886Error<E>> {
+        self.fifo.layout = FifoLayout::from_fifo_en(self.read_byte(FIFO_EN).await?);
+        Ok(self.fifo.layout)
+    }
+
+    #[cfg(feature = "float")]
+    /// Layout set by `enable_fifo` or read by `get_fifo_layout`, FIFO_EN is read if neither was
+    /// called
+    async fn known_fifo_layout(&mut self) -> Result<Option<FifoLayout>, Mpu6886Error<E>> {
+        match self.fifo.layout {
+            Some(layout) => Ok(Some(layout)),
+            None => self.get_fifo_layout().await,
+        }
+    }
+
+    /// set behaviour of a full FIFO (`FIFO_MODE` of CONFIG)
+    pub async fn set_fifo_mode(&mut self, mode: FifoMode) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(CONFIG::ADDR, CONFIG::FIFO_MODE, mode.bit()).await
+    }
+
+    /// get behaviour of a full FIFO (`FIFO_MODE` of CONFIG)
+    pub async fn get_fifo_mode(&mut self) -> Result<FifoMode, Mpu6886Error<E>> {
+        Ok(FifoMode::from_config(self.read_byte(CONFIG::ADDR).await?))
+    }
+
+    /// Resets the FIFO (FIFO_RST of USER_CTRL), all data is discarded
+    pub async fn reset_fifo(&mut self) -> Result<(), Mpu6886Error<E>> {
+        self.fifo.fifo_reset();
+        self.write_bit(USER_CTRL, fifo::FIFO_RST, true).await
+    }
+
+    /// get number of bytes in the FIFO, see `Mpu6886::get_fifo_count`
+    pub async fn get_fifo_count(&mut self) -> Result<u16, Mpu6886Error<E>> {
+        let mut buf = [0; 2];
+        self.read_bytes(FIFO_COUNTH, &mut buf).await?;
+        Ok(fifo::fifo_count(&buf))
+    }
+
+    /// get pending interrupts, decoded from one read of INT_STATUS, which clears it
+    pub async fn get_interrupt_status(&mut self) -> Result<InterruptStatus, Mpu6886Error<E>> {
+        Ok(InterruptStatus::from(self.read_byte(INT_STATUS::ADDR).await?))
+    }
+
+    #[cfg(feature = "float")]
+    /// Reads all complete packets from the FIFO into samples, see `Mpu6886::drain_fifo`
+    ///
+    /// Same packet layouts, overflow detection from the FIFO count and resync, the samples
+    /// are not calibrated.
+    pub async fn drain_fifo(&mut self, samples: &mut [Sample]) -> Result<FifoDrain, Mpu6886Error<E>> {
+        self.drain(samples, None).await
+    }
+
+    #[cfg(feature = "float")]
+    /// Same as `drain_fifo`, also reads (and clears) INT_STATUS, see `Mpu6886::drain_fifo_with_status`
+    pub async fn drain_fifo_with_status(&mut self, samples: &mut [Sample]) -> Result<FifoDrain, Mpu6886Error<E>> {
+        let interrupts = self.get_interrupt_status().await?;
+        self.drain(samples, Some(interrupts)).await
+    }
+
+    #[cfg(feature = "float")]
+    async fn drain(&mut self, samples: &mut [Sample], interrupts: Option<InterruptStatus>) -> Result<FifoDrain, Mpu6886Error<E>> {
+        let drain = FifoDrain::start(core::mem::take(&mut self.fifo.reset), interrupts);
+        let Some(layout) = self.known_fifo_layout().await? else {
+            return Ok(drain);
+        };
+
+        let mut plan = DrainPlan::new(layout, self.get_fifo_count().await?, drain, self.fifo.overflow_left);
+        if plan.recount() {
+            plan.set_count(self.get_fifo_count().await?);
+        }
+        let mode = if plan.overflow() { Some(self.get_fifo_mode().await?) } else { None };
+        let Some(packets) = plan.packets(mode, samples.len()) else {
+            self.reset_fifo().await?;
+            return Ok(plan.drain());
+        };
+
+        let samples = &mut samples[..packets];
+        let mut buf = [0u8; FIFO_READ_CHUNK];
+        let mut done = 0;
+        while done < samples.len() {
+            let bytes = &mut buf[..fifo::chunk_len(layout, samples.len() - done)];
+            self.read_bytes(FIFO_R_W, bytes).await?;
+            done += fifo::decode_packets(layout, bytes, &mut samples[done..], self.acc_sensitivity, self.gyro_sensitivity);
+        }
+        if plan.finish(done) {
+            self.reset_fifo().await?;
+        }
+        self.fifo.overflow_left = plan.overflow_left();
+        Ok(plan.drain())
+    }
+
+    #[cfg(feature = "float")]
+    /// Read sensor data from FIFO in one go, see `Mpu6886::read_fifo`
+    ///
+    /// Fails with `UnsupportedFifoLayout` unless accel and gyro are enabled in the FIFO.
+    pub async fn read_fifo(&mut self) -> Result<Vector3<Vector3<f32>>, Mpu6886Error<E>> {
+        fifo::expect_layout(self.known_fifo_layout().await?, FifoLayout::AccelTempGyro)?;
+        fifo::expect_packet(self.get_fifo_count().await?)?;
+        let mut buf: [u8; 14] = [0; 14];
+        self.read_bytes(FIFO_R_W, &mut buf).await?;
+        Ok(fifo_data(&buf, self.acc_sensitivity, self.gyro_sensitivity))
+    }
+
+    #[cfg(feature = "float")]
+    /// Same as `read_fifo`, in m/s^2 and rad/s
+    pub async fn read_fifo_si(&mut self) -> Result<Vector3<Vector3<f32>>, Mpu6886Error<E>> {
+        Ok(fifo_data_si(self.read_fifo().await?))
+    }
+
+    /// Writes byte to register
+    pub async fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), Mpu6886Error<E>> {
+        self.i2c.write(self.slave_addr, &[reg, byte]).await
+            .map_err(Mpu6886Error::I2c)
+    }
+
+    /// Enables bit n at register address reg
+    pub async fn write_bit(&mut self, reg: u8, bit_n: u8, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        let mut byte = self.read_byte(reg).await?;
+        bits::set_bit(&mut byte, bit_n, enable);
+        self.write_byte(reg, byte).await
+    }
+
+    /// Write bits data at reg from start_bit to start_bit+length
+    pub async fn write_bits(&mut self, reg: u8, start_bit: u8, length: u8, data: u8) -> Result<(), Mpu6886Error<E>> {
+        let mut byte = self.read_byte(reg).await?;
+        bits::set_bits(&mut byte, start_bit, length, data);
+        self.write_byte(reg, byte).await
+    }
+
+    /// Read bit n from register
+    async fn read_bit(&mut self, reg: u8, bit_n: u8) -> Result<u8, Mpu6886Error<E>> {
+        let byte = self.read_byte(reg).await?;
+        Ok(bits::get_bit(byte, bit_n))
+    }
+
+    /// Read bits at register reg, starting with bit start_bit, until start_bit+length
+    pub async fn read_bits(&mut self, reg: u8, start_bit: u8, length: u8) -> Result<u8, Mpu6886Error<E>> {
+        let byte = self.read_byte(reg).await?;
+        Ok(bits::get_bits(byte, start_bit, length))
+    }
+
+    /// Reads byte from register
+    pub async fn read_byte(&mut self, reg: u8) -> Result<u8, Mpu6886Error<E>> {
+        let mut byte: [u8; 1] = [0; 1];
+        self.read_bytes(reg, &mut byte).await?;
+        Ok(byte[0])
+    }
+
+    /// Reads series of bytes into buf from specified reg
+    pub async fn read_bytes(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Mpu6886Error<E>> {
+        self.i2c.write_read(self.slave_addr, &[reg], buf).await
+            .map_err(Mpu6886Error::I2c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use std::vec;
+
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+    /// Polls f to completion, the mocks never return Pending
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = pin!(f);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(out) = f.as_mut().poll(&mut cx) {
+                return out;
+            }
+        }
+    }
+
+    #[test]
+    fn init() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+            Transaction::write_read(ADDR, vec![WHOAMI], vec![0x19]),
+            Transaction::write_read(ADDR, vec![ACCEL_CONFIG::ADDR], vec![0x18]),
+            Transaction::write(ADDR, vec![ACCEL_CONFIG::ADDR, 0x00]),
+            Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x18]),
+            Transaction::write(ADDR, vec![GYRO_CONFIG::ADDR, 0x00]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        block_on(mpu.init(&mut NoopDelay::new())).unwrap();
+
+        i2c.done();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn acc_and_fifo() {
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(ADDR, vec![ACC_REGX_H], vec![0x40, 0x00, 0xc0, 0x00, 0x20, 0x00]),
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x18]),
+            Transaction::write_read(ADDR, vec![FIFO_COUNTH], vec![0x00, 0x0e]),
+            Transaction::write_read(ADDR, vec![FIFO_R_W], vec![
+                0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+                0x00, 0x83, 0x00, 0x00, 0x00, 0x00,
+            ]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        let acc = block_on(mpu.get_acc()).unwrap();
+        assert_eq!((acc.x, acc.y, acc.z), (1.0, -1.0, 0.5));
+        let data = block_on(mpu.read_fifo()).unwrap();
+        assert_eq!(data[0].x, 1.0);
+        assert_eq!(data[1].x, 1.0);
+        assert_eq!(data[2].x, TEMP_OFFSET);
+
+        i2c.done();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn drain_fifo() {
+        let mut i2c = Mock::new(&[
+            // enable_fifo(true, false)
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x00]),
+            Transaction::write(ADDR, vec![FIFO_EN, 0x08]),
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x08]),
+            Transaction::write(ADDR, vec![FIFO_EN, 0x08]),
+            Transaction::write_read(ADDR, vec![USER_CTRL], vec![0x00]),
+            Transaction::write(ADDR, vec![USER_CTRL, 0x01]),
+            Transaction::write_read(ADDR, vec![USER_CTRL], vec![0x01]),
+            Transaction::write(ADDR, vec![USER_CTRL, 0x05]),
+            Transaction::write_read(ADDR, vec![USER_CTRL], vec![0x05]),
+            Transaction::write(ADDR, vec![USER_CTRL, 0x45]),
+            // a packet being written, complete on the second count
+            Transaction::write_read(ADDR, vec![FIFO_COUNTH], vec![0x00, 0x0d]),
+            Transaction::write_read(ADDR, vec![FIFO_COUNTH], vec![0x00, 0x12]),
+            Transaction::write_read(ADDR, vec![FIFO_R_W], vec![
+                0x40, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0xc0, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0x20, 0x00,
+            ]),
+            // full FIFO in overwrite mode: reset
+            Transaction::write_read(ADDR, vec![FIFO_COUNTH], vec![0x04, 0x00]),
+            Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x00]),
+            Transaction::write_read(ADDR, vec![USER_CTRL], vec![0x40]),
+            Transaction::write(ADDR, vec![USER_CTRL, 0x44]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        block_on(mpu.enable_fifo(true, false)).unwrap();
+        assert!(matches!(block_on(mpu.read_fifo()),
+                         Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+
+        let mut samples = [Sample::default(); 4];
+        let drain = block_on(mpu.drain_fifo(&mut samples)).unwrap();
+        assert_eq!((drain.samples, drain.pending, drain.reset, drain.overflow), (3, 0, true, false));
+        assert_eq!(samples[0], Sample { accel: Some(Vector3::new(1.0, 0.0, 0.0)), gyro: None, temp: None });
+        assert_eq!(samples[1].accel, Some(Vector3::new(0.0, -1.0, 0.0)));
+        assert_eq!(samples[2].accel, Some(Vector3::new(0.0, 0.0, 0.5)));
+
+        let drain = block_on(mpu.drain_fifo(&mut samples)).unwrap();
+        assert!(drain.lost_data() && !drain.reset);
+        assert_eq!(drain.samples, 0);
+
+        i2c.done();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn drain_fifo_with_status() {
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(ADDR, vec![INT_STATUS::ADDR], vec![0x11]),
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x10]),
+            Transaction::write_read(ADDR, vec![FIFO_COUNTH], vec![0x00, 0x08]),
+            // overflow in the status, overwrite mode: reset
+            Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x00]),
+            Transaction::write_read(ADDR, vec![USER_CTRL], vec![0x40]),
+            Transaction::write(ADDR, vec![USER_CTRL, 0x44]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        let drain = block_on(mpu.drain_fifo_with_status(&mut [Sample::default(); 2])).unwrap();
+        let interrupts = drain.interrupts.unwrap();
+        assert!(interrupts.fifo_overflow && interrupts.data_ready);
+        assert!(drain.lost_data() && drain.samples == 0);
+
+        i2c.done();
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn reset_device() {
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x18]),
+            Transaction::write_read(ADDR, vec![PWR_MGMT_1::ADDR], vec![0x01]),
+            Transaction::write(ADDR, vec![PWR_MGMT_1::ADDR, 0x81]),
+            // FIFO_EN cleared by the reset, read again instead of the cached layout
+            Transaction::write_read(ADDR, vec![FIFO_EN], vec![0x00]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        assert_eq!(block_on(mpu.get_fifo_layout()).unwrap(), Some(FifoLayout::AccelTempGyro));
+        block_on(mpu.reset_device(&mut NoopDelay::new())).unwrap();
+        let drain = block_on(mpu.drain_fifo(&mut [Sample::default(); 2])).unwrap();
+        assert!(drain.reset && drain.samples == 0);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn gyro_bw() {
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x40]),
+            Transaction::write(ADDR, vec![CONFIG::ADDR, 0x43]),
+            Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x1b]),
+            Transaction::write(ADDR, vec![GYRO_CONFIG::ADDR, 0x18]),
+        ]);
+
+        let mut mpu = Mpu6886Async::new(i2c.clone());
+        block_on(mpu.set_gyro_bw(GyroBw::Hz41)).unwrap();
+
+        i2c.done();
+    }
+}
@@ -0,0 +1,1083 @@
+//! Sensor calibration
+//!
+//! [`Mpu6886::calibrate_gyro`] averages the gyro of the sensor at rest and writes the bias into
+//! the gyro user offset registers, so all readings (data registers and FIFO) are corrected by
+//! the sensor itself. Samples deviating too much are taken as motion and the run is rejected.
+//!
+//! The accelerometer is calibrated in six orientations, each axis pointing up and down once.
+//! [`SixPositionCalibration`] solves the averaged readings for offset, scale and cross-axis
+//! misalignment, the resulting [`Calibration`] is applied by the driver once set:
+//!
+//! ```ignore
+//! let mut six = SixPositionCalibration::new();
+//! for face in Face::ALL {
+//!     wait_for_user(face);
+//!     mpu.measure_accel_face(&mut delay, &mut six, face, 200)?;
+//! }
+//! mpu.set_calibration(Some(six.solve()?));
+//! ```
+//!
+//! The accel offset of the calibration can be moved into the accel offset registers with
+//! `push_accel_offset_to_hardware`, then FIFO and raw data arrive already offset corrected.
+//! The calibration keeps the pushed registers, so a persisted calibration restores them.
+//!
+//! Bias drift over die temperature is recorded during a warm-up run with [`TempDriftRecorder`]
+//! and fitted into [`TempCoefficients`]. The driver compensates scaled readings with the
+//! temperature of the same burst or FIFO packet:
+//!
+//! ```ignore
+//! let mut recorder = TempDriftRecorder::new();
+//! while warming_up() {
+//!     mpu.record_temp_drift(&mut delay, &mut recorder, 50)?;
+//! }
+//! let mut calibration = mpu.calibration().unwrap_or_default();
+//! calibration.temp = recorder.fit(calibration_temp)?;
+//! mpu.set_calibration(Some(calibration));
+//! ```
+//!
+//! A `Calibration` encodes into a fixed size, versioned and checksummed blob of
+//! [`CALIBRATION_BLOB_SIZE`] bytes to persist it, e.g. in flash. After a reboot it is restored
+//! with [`Mpu6886::apply_calibration`]:
+//!
+//! ```ignore
+//! flash.write(&mpu.calibration().unwrap_or_default().encode());
+//! // after reboot
+//! mpu.apply_calibration(&Calibration::decode(&flash.read())?)?;
+//! ```
+//!
+//! All blob values are little endian, floats as IEEE 754 `f32`:
+//!
+//! | offset | size | content |
+//! |-------:|-----:|---------|
+//! |      0 |    2 | magic `"M6"` |
+//! |      2 |    1 | format version ([`CALIBRATION_VERSION`]) |
+//! |      3 |    1 | flags, bit 0: accel offset registers present |
+//! |      4 |    6 | gyro offset registers x, y, z (`i16`) |
+//! |     10 |   12 | accel offset x, y, z |
+//! |     22 |   12 | accel scale x, y, z |
+//! |     34 |   36 | accel misalignment, row major |
+//! |     70 |    4 | temperature coefficients reference temperature |
+//! |     74 |   24 | gyro temperature coefficients, linear x, y, z, quadratic x, y, z |
+//! |     98 |   24 | accel temperature coefficients, linear x, y, z, quadratic x, y, z |
+//! |    122 |   16 | mounting rotation quaternion w, i, j, k |
+//! |    138 |    6 | accel offset registers x, y, z (`i16`), 0 if not present |
+//! |    144 |    2 | CRC-16/CCITT-FALSE of bytes 0 to 143 |
+
+use libm::{ceilf, roundf, sqrtf};
+use nalgebra::{Matrix3, Quaternion, SMatrix, UnitQuaternion, Vector3};
+
+use crate::device::*;
+use crate::error::*;
+use crate::interface::{Delay, Interface};
+use crate::raw::{self, ImuSample};
+use crate::{Mpu6886, PI_180};
+
+/// Standard deviation of the gyro in rad/s (0.5 dps) above which `calibrate_gyro` rejects
+/// the run as motion
+pub const GYRO_CAL_MAX_STD: f32 = 0.5 * PI_180;
+
+/// Standard deviation of the accelerometer in g above which `measure_accel_face` rejects
+/// the measurement as motion
+pub const ACCEL_CAL_MAX_STD: f32 = 0.05;
+
+/// Sensor orientation of the six position calibration, the named axis points up or down
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    /// x axis up
+    XUp = 0,
+    /// x axis down
+    XDown,
+    /// y axis up
+    YUp,
+    /// y axis down
+    YDown,
+    /// z axis up, flat on a table
+    ZUp,
+    /// z axis down
+    ZDown,
+}
+
+impl Face {
+    /// All faces, in the order of the enum
+    pub const ALL: [Face; 6] = [Face::XUp, Face::XDown, Face::YUp, Face::YDown, Face::ZUp, Face::ZDown];
+
+    /// Accelerometer reading in g of an ideal sensor at rest in this orientation
+    pub fn gravity(self) -> Vector3<f32> {
+        match self {
+            Face::XUp => Vector3::x(),
+            Face::XDown => -Vector3::x(),
+            Face::YUp => Vector3::y(),
+            Face::YDown => -Vector3::y(),
+            Face::ZUp => Vector3::z(),
+            Face::ZDown => -Vector3::z(),
+        }
+    }
+
+    /// The same axis pointing the other way
+    pub fn opposite(self) -> Face {
+        Face::ALL[self as usize ^ 1]
+    }
+}
+
+/// Magic bytes at the start of a calibration blob
+pub const CALIBRATION_MAGIC: [u8; 2] = *b"M6";
+
+/// Version of the calibration blob format
+pub const CALIBRATION_VERSION: u8 = 1;
+
+/// Size of a calibration blob in bytes
+pub const CALIBRATION_BLOB_SIZE: usize = 146;
+
+/// Bias drift over die temperature, per axis
+///
+/// drift(t) = linear * (t - reference) + quadratic * (t - reference)²
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempCoefficients {
+    /// Temperature without drift in degrees celcius, usually the calibration temperature
+    pub reference: f32,
+    /// Gyro drift in rad/s per °C (linear) and rad/s per °C² (quadratic)
+    pub gyro: [Vector3<f32>; 2],
+    /// Accel drift in g per °C (linear) and g per °C² (quadratic)
+    pub accel: [Vector3<f32>; 2],
+}
+
+impl TempCoefficients {
+    /// Gyro drift at temp in rad/s
+    pub fn gyro_drift(&self, temp: f32) -> Vector3<f32> {
+        let dt = temp - self.reference;
+        self.gyro[0] * dt + self.gyro[1] * (dt * dt)
+    }
+
+    /// Accel drift at temp in g
+    pub fn accel_drift(&self, temp: f32) -> Vector3<f32> {
+        let dt = temp - self.reference;
+        self.accel[0] * dt + self.accel[1] * (dt * dt)
+    }
+
+    /// Any non zero coefficient
+    pub fn has_drift(&self) -> bool {
+        self.gyro.iter().chain(self.accel.iter()).any(|c| c.amax() != 0.0)
+    }
+}
+
+impl Default for TempCoefficients {
+    /// No drift
+    fn default() -> Self {
+        TempCoefficients {
+            reference: TEMP_OFFSET,
+            gyro: [Vector3::zeros(); 2],
+            accel: [Vector3::zeros(); 2],
+        }
+    }
+}
+
+/// Sensor calibration, set with `Mpu6886::apply_calibration`
+///
+/// The gyro offset and the pushed accel offset registers are written to the sensor, the accel
+/// correction, the temperature drift and the mounting rotation are applied by the driver to all
+/// scaled readings:
+///
+/// acc = mounting * misalignment * (scale ∘ (acc - accel_drift(t) - offset)), with acc in g,
+/// gyro = mounting * (gyro - gyro_drift(t))
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Gyro offset registers, see `calibrate_gyro`
+    pub gyro_offset: GyroOffset,
+    /// Accelerometer bias in g
+    pub accel_offset: Vector3<f32>,
+    /// Accelerometer scale factor per axis
+    pub accel_scale: Vector3<f32>,
+    /// Accelerometer cross-axis correction, unit diagonal
+    pub accel_misalignment: Matrix3<f32>,
+    /// Bias drift over die temperature
+    pub temp: TempCoefficients,
+    /// Rotation from the sensor frame into the frame of the device it is mounted in
+    pub mounting: UnitQuaternion<f32>,
+    /// Accel offset registers set by `push_accel_offset_to_hardware`, None keeps the factory trim
+    pub accel_offset_registers: Option<AccelOffset>,
+}
+
+impl Default for Calibration {
+    /// No correction
+    fn default() -> Self {
+        Calibration {
+            gyro_offset: GyroOffset::default(),
+            accel_offset: Vector3::zeros(),
+            accel_scale: Vector3::repeat(1.0),
+            accel_misalignment: Matrix3::identity(),
+            temp: TempCoefficients::default(),
+            mounting: UnitQuaternion::identity(),
+            accel_offset_registers: None,
+        }
+    }
+}
+
+impl Calibration {
+    /// Corrects an accelerometer reading in g
+    pub fn apply_accel(&self, acc: Vector3<f32>) -> Vector3<f32> {
+        self.mounting * (self.accel_misalignment * (acc - self.accel_offset).component_mul(&self.accel_scale))
+    }
+
+    /// Rotates a gyro reading into the mounting frame, the offset is corrected by the sensor
+    pub fn apply_gyro(&self, gyro: Vector3<f32>) -> Vector3<f32> {
+        self.mounting * gyro
+    }
+
+    /// Encodes into the calibration blob, see the [module documentation](self)
+    pub fn encode(&self) -> [u8; CALIBRATION_BLOB_SIZE] {
+        let mut blob = [0; CALIBRATION_BLOB_SIZE];
+        let mut w = BlobWriter { blob: &mut blob, pos: 0 };
+        w.bytes(&CALIBRATION_MAGIC);
+        w.bytes(&[CALIBRATION_VERSION, self.accel_offset_registers.is_some() as u8]);
+        for value in self.gyro_offset.0.iter() {
+            w.bytes(&value.to_le_bytes());
+        }
+        w.floats(self.accel_offset.iter());
+        w.floats(self.accel_scale.iter());
+        w.floats(self.accel_misalignment.transpose().iter());
+        w.floats([self.temp.reference].iter());
+        w.floats(self.temp.gyro.iter().flat_map(|c| c.iter()));
+        w.floats(self.temp.accel.iter().flat_map(|c| c.iter()));
+        let q = self.mounting.quaternion();
+        w.floats([q.w, q.i, q.j, q.k].iter());
+        for value in self.accel_offset_registers.unwrap_or_default().0.iter() {
+            w.bytes(&value.to_le_bytes());
+        }
+
+        let crc = crc16(&blob[..CALIBRATION_BLOB_SIZE - 2]);
+        blob[CALIBRATION_BLOB_SIZE - 2..].copy_from_slice(&crc.to_le_bytes());
+        blob
+    }
+
+    /// Decodes a calibration blob
+    ///
+    /// Fails with `InvalidCalibrationBlob` on wrong magic or an unknown version, with
+    /// `CalibrationChecksum` if the blob is corrupted.
+    pub fn decode(blob: &[u8; CALIBRATION_BLOB_SIZE]) -> Result<Self, SensorError> {
+        if blob[..2] != CALIBRATION_MAGIC || blob[2] != CALIBRATION_VERSION {
+            return Err(SensorError::InvalidCalibrationBlob);
+        }
+        let crc = u16::from_le_bytes([blob[CALIBRATION_BLOB_SIZE - 2], blob[CALIBRATION_BLOB_SIZE - 1]]);
+        if crc != crc16(&blob[..CALIBRATION_BLOB_SIZE - 2]) {
+            return Err(SensorError::CalibrationChecksum);
+        }
+
+        let mut r = BlobReader { blob, pos: 4 };
+        let gyro_offset = GyroOffset(r.words());
+        let accel_offset = r.vector();
+        let accel_scale = r.vector();
+        let accel_misalignment = Matrix3::from_fn(|_, _| r.float()).transpose();
+        let temp = TempCoefficients {
+            reference: r.float(),
+            gyro: [r.vector(), r.vector()],
+            accel: [r.vector(), r.vector()],
+        };
+        let (w, i, j, k) = (r.float(), r.float(), r.float(), r.float());
+        let accel_offset_registers = AccelOffset(r.words());
+        Ok(Calibration {
+            gyro_offset,
+            accel_offset,
+            accel_scale,
+            accel_misalignment,
+            temp,
+            mounting: UnitQuaternion::new_normalize(Quaternion::new(w, i, j, k)),
+            accel_offset_registers: (blob[3] & 1 != 0).then_some(accel_offset_registers),
+        })
+    }
+}
+
+/// Sequential little endian writes into a calibration blob
+struct BlobWriter<'a> {
+    blob: &'a mut [u8; CALIBRATION_BLOB_SIZE],
+    pos: usize,
+}
+
+impl BlobWriter<'_> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.blob[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    fn floats<'f>(&mut self, values: impl Iterator<Item = &'f f32>) {
+        for value in values {
+            self.bytes(&value.to_le_bytes());
+        }
+    }
+}
+
+/// Sequential little endian reads from a calibration blob
+struct BlobReader<'a> {
+    blob: &'a [u8; CALIBRATION_BLOB_SIZE],
+    pos: usize,
+}
+
+impl BlobReader<'_> {
+    fn byte(&mut self) -> u8 {
+        self.pos += 1;
+        self.blob[self.pos - 1]
+    }
+
+    fn float(&mut self) -> f32 {
+        f32::from_le_bytes([self.byte(), self.byte(), self.byte(), self.byte()])
+    }
+
+    fn vector(&mut self) -> Vector3<f32> {
+        Vector3::from_fn(|_, _| self.float())
+    }
+
+    fn words(&mut self) -> Vector3<i16> {
+        Vector3::from_fn(|_, _| i16::from_le_bytes([self.byte(), self.byte()]))
+    }
+}
+
+/// CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xffff)
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Averaged accelerometer readings of the six faces, solved into a `Calibration`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SixPositionCalibration {
+    faces: [Option<Vector3<f32>>; 6],
+}
+
+impl SixPositionCalibration {
+    /// No face measured yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the averaged, uncorrected reading in g of face, see `measure_accel_face`
+    pub fn add(&mut self, face: Face, acc: Vector3<f32>) {
+        self.faces[face as usize] = Some(acc);
+    }
+
+    /// Faces without reading yet
+    pub fn missing(&self) -> impl Iterator<Item = Face> + '_ {
+        Face::ALL.into_iter().filter(|face| self.faces[*face as usize].is_none())
+    }
+
+    /// All six faces measured
+    pub fn is_complete(&self) -> bool {
+        self.missing().next().is_none()
+    }
+
+    /// Least squares fit of the linear model gravity = A * acc + c over all faces
+    ///
+    /// A is split into misalignment and scale, the offset is -A⁻¹ c. Fails with
+    /// `IncompleteCalibration` if a face is missing or the readings are degenerate.
+    pub fn solve(&self) -> Result<Calibration, SensorError> {
+        let mut x = SMatrix::<f32, 6, 4>::zeros();
+        let mut y = SMatrix::<f32, 6, 3>::zeros();
+        for face in Face::ALL {
+            let acc = self.faces[face as usize].ok_or(SensorError::IncompleteCalibration)?;
+            x.row_mut(face as usize).copy_from(&acc.push(1.0).transpose());
+            y.row_mut(face as usize).copy_from(&face.gravity().transpose());
+        }
+
+        let xtx_inv = (x.transpose() * x).try_inverse().ok_or(SensorError::IncompleteCalibration)?;
+        let w = xtx_inv * x.transpose() * y;
+        let a: Matrix3<f32> = w.fixed_slice::<3, 3>(0, 0).transpose();
+        let c: Vector3<f32> = w.fixed_slice::<1, 3>(3, 0).transpose();
+
+        let a_inv = a.try_inverse().ok_or(SensorError::IncompleteCalibration)?;
+        let scale = a.diagonal();
+        Ok(Calibration {
+            accel_offset: -(a_inv * c),
+            accel_scale: scale,
+            accel_misalignment: a * Matrix3::from_diagonal(&scale.map(|s| 1.0 / s)),
+            ..Calibration::default()
+        })
+    }
+}
+
+/// Records bias over die temperature at rest, fitted into `TempCoefficients`
+///
+/// Each point is an averaged reading at one temperature, see `Mpu6886::record_temp_drift`.
+/// Only the normal equations of the per-axis quadratic fit are kept, so any number of points
+/// can be recorded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TempDriftRecorder {
+    /// temperature all sums are relative to, the first one recorded
+    t0: f32,
+    range: Option<(f32, f32)>,
+    /// sums of u^(i+j), u = t - t0
+    xtx: Matrix3<f32>,
+    /// sums of u^i * gyro (row i)
+    xty_gyro: Matrix3<f32>,
+    /// sums of u^i * accel (row i)
+    xty_accel: Matrix3<f32>,
+    points: u32,
+}
+
+impl TempDriftRecorder {
+    /// No points yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a reading at rest at temp in degrees celcius, gyro in rad/s, accel in g
+    pub fn push(&mut self, temp: f32, gyro: Vector3<f32>, accel: Vector3<f32>) {
+        let (min, max) = *self.range.get_or_insert((temp, temp));
+        if self.points == 0 {
+            self.t0 = temp;
+        }
+        self.range = Some((min.min(temp), max.max(temp)));
+
+        let u = temp - self.t0;
+        let x = Vector3::new(1.0, u, u * u);
+        self.xtx += x * x.transpose();
+        self.xty_gyro += x * gyro.transpose();
+        self.xty_accel += x * accel.transpose();
+        self.points += 1;
+    }
+
+    /// Number of recorded points
+    pub fn len(&self) -> u32 {
+        self.points
+    }
+
+    /// No points recorded
+    pub fn is_empty(&self) -> bool {
+        self.points == 0
+    }
+
+    /// Lowest and highest recorded temperature
+    pub fn temp_range(&self) -> Option<(f32, f32)> {
+        self.range
+    }
+
+    /// Least squares fit of a quadratic per axis, drift is 0 at reference
+    ///
+    /// reference should be the temperature the offsets were calibrated at. Fails with
+    /// `InsufficientTempRange` for less than 3 points or less than 1 °C temperature range.
+    pub fn fit(&self, reference: f32) -> Result<TempCoefficients, SensorError> {
+        let (min, max) = self.range.ok_or(SensorError::InsufficientTempRange)?;
+        if self.points < 3 || max - min < 1.0 {
+            return Err(SensorError::InsufficientTempRange);
+        }
+        let xtx_inv = self.xtx.try_inverse().ok_or(SensorError::InsufficientTempRange)?;
+        // rows: c0 + c1 * u + c2 * u², with u = dt + (reference - t0)
+        let shift = reference - self.t0;
+        let coefficients = |xty: &Matrix3<f32>| {
+            let c = xtx_inv * xty;
+            let (c1, c2) = (c.row(1).transpose(), c.row(2).transpose());
+            [c1 + c2 * (2.0 * shift), c2]
+        };
+        Ok(TempCoefficients {
+            reference,
+            gyro: coefficients(&self.xty_gyro),
+            accel: coefficients(&self.xty_accel),
+        })
+    }
+}
+
+/// Gyro user offset (XG_OFFS_USR, YG_OFFS_USR, ZG_OFFS_USR), added to the gyro output
+///
+/// The registers count in ±1000 dps scale (`GYRO_OFFSET_SENS`) for all gyro ranges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GyroOffset(pub Vector3<i16>);
+
+impl GyroOffset {
+    /// Offset in rad/s, saturated to the register range (±999 dps)
+    pub fn from_rad_s(offset: Vector3<f32>) -> Self {
+        GyroOffset((offset * (GYRO_OFFSET_SENS / PI_180)).map(|v| roundf(v) as i16))
+    }
+
+    /// Offset in rad/s
+    pub fn rad_s(&self) -> Vector3<f32> {
+        self.0.map(f32::from) * (PI_180 / GYRO_OFFSET_SENS)
+    }
+}
+
+/// Accel offset cancellation (XA_OFFSET, YA_OFFSET, ZA_OFFSET), added to the accel output
+///
+/// 15 bit signed values (-16384 to 16383) in steps of `ACCEL_OFFSET_LSB_MG` for all accel
+/// ranges. The registers hold factory trim values, adjust them instead of overwriting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccelOffset(pub Vector3<i16>);
+
+impl AccelOffset {
+    /// Smallest register value
+    pub const MIN: i16 = -(1 << 14);
+    /// Largest register value
+    pub const MAX: i16 = (1 << 14) - 1;
+
+    /// Offset in mg, saturated to the register range (about ±16 g)
+    pub fn from_mg(offset: Vector3<f32>) -> Self {
+        AccelOffset((offset / ACCEL_OFFSET_LSB_MG)
+            .map(|v| roundf(v).clamp(Self::MIN as f32, Self::MAX as f32) as i16))
+    }
+
+    /// Offset in mg
+    pub fn mg(&self) -> Vector3<f32> {
+        self.0.map(f32::from) * ACCEL_OFFSET_LSB_MG
+    }
+}
+
+/// Running mean and variance per axis (Welford)
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct RunningStats {
+    n: u32,
+    mean: Vector3<f32>,
+    m2: Vector3<f32>,
+}
+
+impl RunningStats {
+    pub(crate) fn push(&mut self, x: Vector3<f32>) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f32;
+        self.m2 += delta.component_mul(&(x - self.mean));
+    }
+
+    pub(crate) fn mean(&self) -> Vector3<f32> {
+        self.mean
+    }
+
+    /// Sample standard deviation, needs 2 samples
+    pub(crate) fn std(&self) -> Vector3<f32> {
+        (self.m2 / (self.n - 1) as f32).map(sqrtf)
+    }
+}
+
+impl<I, E> Mpu6886<I>
+where
+    I: Interface<Error = E>,
+{
+    /// set gyro user offsets (XG_OFFS_USR, YG_OFFS_USR, ZG_OFFS_USR)
+    pub fn set_gyro_offset(&mut self, offset: GyroOffset) -> Result<(), Mpu6886Error<E>> {
+        for (reg, value) in [XG_OFFS_USRH, YG_OFFS_USRH, ZG_OFFS_USRH].iter().zip(offset.0.iter()) {
+            let [high, low] = value.to_be_bytes();
+            self.write_byte(*reg, high)?;
+            self.write_byte(*reg + 1, low)?;
+        }
+        Ok(())
+    }
+
+    /// get gyro user offsets (XG_OFFS_USR, YG_OFFS_USR, ZG_OFFS_USR)
+    pub fn get_gyro_offset(&mut self) -> Result<GyroOffset, Mpu6886Error<E>> {
+        let mut buf = [0; 6];
+        self.read_bytes(XG_OFFS_USRH, &mut buf)?;
+        let word = |i: usize| i16::from_be_bytes([buf[2 * i], buf[2 * i + 1]]);
+        Ok(GyroOffset(Vector3::new(word(0), word(1), word(2))))
+    }
+
+    /// set accel offset cancellation (XA_OFFSET, YA_OFFSET, ZA_OFFSET), values beyond
+    /// the 15 bit range are saturated, the reserved bit 0 of the low registers is kept
+    pub fn set_accel_offset(&mut self, offset: AccelOffset) -> Result<(), Mpu6886Error<E>> {
+        for (reg, value) in [XA_OFFSET_H, YA_OFFSET_H, ZA_OFFSET_H].iter().zip(offset.0.iter()) {
+            let [high, low] = (value.clamp(&AccelOffset::MIN, &AccelOffset::MAX) << 1).to_be_bytes();
+            self.write_byte(*reg, high)?;
+            self.write_bits(*reg + 1, 7, 7, low >> 1)?;
+        }
+        Ok(())
+    }
+
+    /// get accel offset cancellation (XA_OFFSET, YA_OFFSET, ZA_OFFSET)
+    pub fn get_accel_offset(&mut self) -> Result<AccelOffset, Mpu6886Error<E>> {
+        let mut offset = Vector3::zeros();
+        for (reg, value) in [XA_OFFSET_H, YA_OFFSET_H, ZA_OFFSET_H].iter().zip(offset.iter_mut()) {
+            let mut buf = [0; 2];
+            self.read_bytes(*reg, &mut buf)?;
+            *value = i16::from_be_bytes(buf) >> 1;
+        }
+        Ok(AccelOffset(offset))
+    }
+
+    /// Removes an accel bias in g in hardware, by adjusting the accel offset registers
+    ///
+    /// Returns the new offset registers, resolution is `ACCEL_OFFSET_LSB_MG`.
+    pub fn adjust_accel_offset(&mut self, bias: Vector3<f32>) -> Result<AccelOffset, Mpu6886Error<E>> {
+        let current = self.get_accel_offset()?;
+        let offset = AccelOffset::from_mg(current.mg() - bias * 1000.0);
+        self.set_accel_offset(offset)?;
+        Ok(offset)
+    }
+
+    /// Moves the accel offset of the calibration into the accel offset registers
+    ///
+    /// Afterwards all accel data, including raw readings and the FIFO, is offset corrected
+    /// by the sensor, the calibration keeps scale and misalignment and records the new
+    /// registers in `accel_offset_registers`. Without calibration, the offset registers are
+    /// left as they are. The registers return to the factory trim on power up, persist the
+    /// calibration after pushing, `apply_calibration` writes them again.
+    pub fn push_accel_offset_to_hardware(&mut self) -> Result<AccelOffset, Mpu6886Error<E>> {
+        match self.calibration {
+            Some(mut calibration) => {
+                let offset = self.adjust_accel_offset(calibration.accel_offset)?;
+                calibration.accel_offset = Vector3::zeros();
+                calibration.accel_offset_registers = Some(offset);
+                self.calibration = Some(calibration);
+                Ok(offset)
+            }
+            None => self.get_accel_offset(),
+        }
+    }
+
+    /// Set the correction applied to the scaled readings, None for no correction
+    ///
+    /// Applies to `get_acc`, `get_gyro`, `read_all`, `read_fifo` and `drain_fifo`, not to raw
+    /// readings. The gyro offset registers are not written, see `apply_calibration`.
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+    }
+
+    /// Correction applied to the scaled readings
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+
+    /// Restores a calibration, e.g. decoded from a calibration blob
+    ///
+    /// Writes the gyro offset registers, the accel offset registers if the calibration has
+    /// them (see `push_accel_offset_to_hardware`) and sets the correction of the scaled readings.
+    pub fn apply_calibration(&mut self, calibration: &Calibration) -> Result<(), Mpu6886Error<E>> {
+        self.set_gyro_offset(calibration.gyro_offset)?;
+        if let Some(offset) = calibration.accel_offset_registers {
+            self.set_accel_offset(offset)?;
+        }
+        self.calibration = Some(*calibration);
+        Ok(())
+    }
+
+    /// Averages samples uncorrected accelerometer readings in g for face of the six
+    /// position calibration, one per sample period, the sensor must be at rest
+    ///
+    /// Fails with `MotionDuringCalibration` if the deviation of an axis is above
+    /// `ACCEL_CAL_MAX_STD`, or `UnexpectedOrientation` if the reading is more than 60°
+    /// off the face.
+    pub fn measure_accel_face<D: Delay<I::Hal>>(&mut self, delay: &mut D, calibration: &mut SixPositionCalibration,
+                                                face: Face, samples: u16) -> Result<Vector3<f32>, Mpu6886Error<E>> {
+        if samples < 2 {
+            return Err(SensorError::NotEnoughSamples.into());
+        }
+        let period_ms = self.sample_period_ms()?;
+
+        let mut stats = RunningStats::default();
+        for _ in 0..samples {
+            delay.delay_ms(period_ms);
+            stats.push(raw::accel_g(self.get_acc_raw()?, self.acc_sensitivity));
+        }
+        if stats.std().max() > ACCEL_CAL_MAX_STD {
+            return Err(SensorError::MotionDuringCalibration.into());
+        }
+        if stats.mean().dot(&face.gravity()) < 0.5 * stats.mean().norm() {
+            return Err(SensorError::UnexpectedOrientation.into());
+        }
+        calibration.add(face, stats.mean());
+        Ok(stats.mean())
+    }
+
+    /// Records one point of bias over die temperature, the sensor must be at rest
+    ///
+    /// Averages samples uncorrected readings, one per sample period, and adds them to
+    /// recorder. Call it repeatedly while the sensor warms up. Fails with
+    /// `MotionDuringCalibration` like `calibrate_gyro` and `measure_accel_face`.
+    /// Returns the averaged reading.
+    pub fn record_temp_drift<D: Delay<I::Hal>>(&mut self, delay: &mut D, recorder: &mut TempDriftRecorder,
+                                               samples: u16) -> Result<ImuSample, Mpu6886Error<E>> {
+        if samples < 2 {
+            return Err(SensorError::NotEnoughSamples.into());
+        }
+        let period_ms = self.sample_period_ms()?;
+
+        let (mut gyro, mut accel, mut temp) = (RunningStats::default(), RunningStats::default(), 0.0);
+        for _ in 0..samples {
+            delay.delay_ms(period_ms);
+            let sample = ImuSample::from_raw(&self.get_raw()?, self.acc_sensitivity, self.gyro_sensitivity);
+            gyro.push(sample.gyro);
+            accel.push(sample.accel);
+            temp += sample.temp / samples as f32;
+        }
+        if gyro.std().max() > GYRO_CAL_MAX_STD || accel.std().max() > ACCEL_CAL_MAX_STD {
+            return Err(SensorError::MotionDuringCalibration.into());
+        }
+        recorder.push(temp, gyro.mean(), accel.mean());
+        Ok(ImuSample { accel: accel.mean(), gyro: gyro.mean(), temp })
+    }
+
+    /// Sample period in whole ms for the calibration delays
+    fn sample_period_ms(&mut self) -> Result<u8, Mpu6886Error<E>> {
+        Ok(ceilf(1000.0 / self.get_sample_rate()?).clamp(1.0, 255.0) as u8)
+    }
+
+    /// Gyro bias calibration, the sensor must be at rest
+    ///
+    /// Averages samples gyro readings, one per sample period, and writes the gyro user offsets
+    /// so that the gyro reads 0 at rest in all ranges. Existing offsets are taken into account.
+    /// Returns the new offsets, or `MotionDuringCalibration` without changing them, if the
+    /// deviation of an axis is above `GYRO_CAL_MAX_STD`. If a calibration is set, its
+    /// `gyro_offset` is updated so it persists the new offsets. Without one, none is set: the
+    /// scaled readings stay uncorrected until `set_calibration` is called.
+    pub fn calibrate_gyro<D: Delay<I::Hal>>(&mut self, delay: &mut D, samples: u16) -> Result<GyroOffset, Mpu6886Error<E>> {
+        self.calibrate_gyro_with_threshold(delay, samples, GYRO_CAL_MAX_STD)
+    }
+
+    /// Same as `calibrate_gyro`, with the motion threshold as standard deviation in rad/s
+    pub fn calibrate_gyro_with_threshold<D: Delay<I::Hal>>(&mut self, delay: &mut D, samples: u16, max_std: f32)
+                                                           -> Result<GyroOffset, Mpu6886Error<E>> {
+        if samples < 2 {
+            return Err(SensorError::NotEnoughSamples.into());
+        }
+        let range = self.get_gyro_range()?;
+        let offset = self.get_gyro_offset()?;
+        let period_ms = self.sample_period_ms()?;
+
+        let mut stats = RunningStats::default();
+        for _ in 0..samples {
+            delay.delay_ms(period_ms);
+            stats.push(self.get_gyro_raw()?.map(f32::from));
+        }
+        if stats.std().max() > max_std / PI_180 * range.sensitivity() {
+            return Err(SensorError::MotionDuringCalibration.into());
+        }
+
+        // output counts of range to offset counts
+        let scale = (1 << range as u8) as f32 / 4.0;
+        let offset = GyroOffset((offset.0.map(f32::from) - stats.mean() * scale).map(|v| roundf(v) as i16));
+        self.set_gyro_offset(offset)?;
+        if let Some(calibration) = &mut self.calibration {
+            calibration.gyro_offset = offset;
+        }
+        Ok(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Mpu6886Sim;
+
+    #[test]
+    fn gyro_offset() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+
+        let offset = GyroOffset(Vector3::new(-2, 300, i16::MIN));
+        mpu.set_gyro_offset(offset).unwrap();
+        assert_eq!((sim.register(XG_OFFS_USRH), sim.register(XG_OFFS_USRL)), (0xff, 0xfe));
+        assert_eq!((sim.register(ZG_OFFS_USRH), sim.register(ZG_OFFS_USRL)), (0x80, 0x00));
+        assert_eq!(mpu.get_gyro_offset().unwrap(), offset);
+
+        assert_eq!(GyroOffset::from_rad_s(Vector3::new(PI_180, -10.0 * PI_180, 0.0)).0, Vector3::new(33, -328, 0));
+        assert_eq!(GyroOffset::from_rad_s(Vector3::repeat(20.0)).0, Vector3::repeat(i16::MAX));
+        assert!((GyroOffset(Vector3::new(328, 0, 0)).rad_s().x - 10.0 * PI_180).abs() < 1e-6);
+    }
+
+    #[test]
+    fn calibrate_gyro() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        let bias = Vector3::new(0.02, -0.05, 0.1);
+        let mut delay = sim.sampling_delay(|sim, n| {
+            let noise = if n % 2 == 0 { 0.002 } else { -0.002 };
+            sim.set_gyro(bias.add_scalar(noise));
+        });
+
+        for range in [GyroRange::D250, GyroRange::D2000] {
+            mpu.set_gyro_range(range).unwrap();
+            let offset = mpu.calibrate_gyro(&mut delay, 50).unwrap();
+            assert!((offset.rad_s() + bias).amax() < 1e-3);
+            assert_eq!(mpu.get_gyro_offset().unwrap(), offset);
+            // the offsets are in the hardware only, no calibration is set
+            assert_eq!(mpu.calibration(), None);
+
+            sim.set_gyro(bias);
+            sim.sample();
+            assert!(mpu.get_gyro().unwrap().amax() < 2e-3);
+        }
+    }
+
+    /// Distorted sensor: reading = D * acc + bias
+    fn distorted(acc: Vector3<f32>) -> Vector3<f32> {
+        let d = Matrix3::new(1.02, 0.01, -0.005,
+                             0.004, 0.97, 0.012,
+                             -0.01, 0.006, 1.03);
+        d * acc + Vector3::new(0.03, -0.02, 0.05)
+    }
+
+    #[test]
+    fn six_position_solve() {
+        let mut six = SixPositionCalibration::new();
+        assert!(matches!(six.solve(), Err(SensorError::IncompleteCalibration)));
+        for face in Face::ALL {
+            six.add(face, distorted(face.gravity()));
+        }
+        assert!(six.is_complete());
+        let cal = six.solve().unwrap();
+
+        assert!((cal.accel_offset - Vector3::new(0.03, -0.02, 0.05)).amax() < 1e-4);
+        assert_eq!(cal.accel_misalignment.diagonal(), Vector3::repeat(1.0));
+        for acc in [Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.3, -0.5, 0.81), Vector3::new(-2.0, 1.0, 0.5)] {
+            assert!((cal.apply_accel(distorted(acc)) - acc).amax() < 1e-4);
+        }
+        assert_eq!(Calibration::default().apply_accel(Vector3::new(0.1, 0.2, 0.3)), Vector3::new(0.1, 0.2, 0.3));
+    }
+
+    #[test]
+    fn six_position_calibration() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        let mut six = SixPositionCalibration::new();
+
+        for face in Face::ALL {
+            let mut delay = sim.sampling_delay(|sim, n| {
+                let noise = if n % 2 == 0 { 0.001 } else { -0.001 };
+                sim.set_accel(distorted(face.gravity()).add_scalar(noise));
+            });
+            assert!(matches!(mpu.measure_accel_face(&mut delay, &mut six, face.opposite(), 10),
+                             Err(Mpu6886Error::SensorError(SensorError::UnexpectedOrientation))));
+            mpu.measure_accel_face(&mut delay, &mut six, face, 10).unwrap();
+        }
+        assert_eq!(six.missing().count(), 0);
+        let mut delay = sim.sampling_delay(|sim, n| sim.set_accel(Vector3::new(0.0, 0.0, 1.0 + n as f32 * 0.05)));
+        assert!(matches!(mpu.measure_accel_face(&mut delay, &mut six, Face::ZUp, 10),
+                         Err(Mpu6886Error::SensorError(SensorError::MotionDuringCalibration))));
+
+        // applied to all scaled readings
+        mpu.set_calibration(Some(six.solve().unwrap()));
+        let acc = Vector3::new(0.6, 0.0, 0.8);
+        mpu.enable_fifo(true, true).unwrap();
+        sim.set_accel(distorted(acc));
+        sim.sample();
+        sim.sample();
+        assert!((mpu.get_acc().unwrap() - acc).amax() < 1e-3);
+        assert!((mpu.read_all().unwrap().accel - acc).amax() < 1e-3);
+        assert!((mpu.read_fifo().unwrap()[0] - acc).amax() < 1e-3);
+        let mut samples = [crate::Sample::default(); 2];
+        assert_eq!(mpu.drain_fifo(&mut samples).unwrap().samples, 1);
+        assert!((samples[0].accel.unwrap() - acc).amax() < 1e-3);
+
+        mpu.set_calibration(None);
+        assert!((mpu.get_acc().unwrap() - distorted(acc)).amax() < 1e-3);
+    }
+
+    #[test]
+    fn accel_offset() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        sim.set_register(XA_OFFSET_L, 0x01);
+
+        let offset = AccelOffset(Vector3::new(-1, 1000, AccelOffset::MAX));
+        mpu.set_accel_offset(offset).unwrap();
+        assert_eq!((sim.register(XA_OFFSET_H), sim.register(XA_OFFSET_L)), (0xff, 0xff));
+        assert_eq!((sim.register(YA_OFFSET_H), sim.register(YA_OFFSET_L)), (0x07, 0xd0));
+        assert_eq!((sim.register(ZA_OFFSET_H), sim.register(ZA_OFFSET_L)), (0x7f, 0xfe));
+        assert_eq!(mpu.get_accel_offset().unwrap(), offset);
+        mpu.set_accel_offset(AccelOffset(Vector3::new(0, i16::MIN, 0))).unwrap();
+        assert_eq!(sim.register(XA_OFFSET_L), 0x01);
+        assert_eq!(mpu.get_accel_offset().unwrap().0.y, AccelOffset::MIN);
+
+        assert_eq!(AccelOffset::from_mg(Vector3::new(1000.0, -1.0, 20_000.0)).0, Vector3::new(1024, -1, AccelOffset::MAX));
+        assert_eq!(AccelOffset(Vector3::new(512, 0, 0)).mg().x, 500.0);
+    }
+
+    #[test]
+    fn push_accel_offset_to_hardware() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        mpu.enable_fifo(true, false).unwrap();
+        // factory trim of 100 mg on x, compensated by the factory
+        sim.set_register(XA_OFFSET_L, 0x01);
+        mpu.set_accel_offset(AccelOffset(Vector3::new(102, 0, 0))).unwrap();
+        sim.set_accel(Vector3::new(-0.1, 0.0, 0.0));
+
+        assert_eq!(mpu.push_accel_offset_to_hardware().unwrap().0, Vector3::new(102, 0, 0));
+        let mut six = SixPositionCalibration::new();
+        for face in Face::ALL {
+            six.add(face, distorted(face.gravity()));
+        }
+        mpu.set_calibration(Some(six.solve().unwrap()));
+        let offset = mpu.push_accel_offset_to_hardware().unwrap();
+        // 102 - 30.7, 20.5, -51.2 LSB
+        assert_eq!(offset.0, Vector3::new(71, 20, -51));
+        assert_eq!(mpu.calibration().unwrap().accel_offset, Vector3::zeros());
+        assert_eq!(mpu.calibration().unwrap().accel_offset_registers, Some(offset));
+        assert_eq!(sim.register(XA_OFFSET_L) & 1, 1);
+
+        // FIFO data is offset corrected by the sensor, scale and misalignment in software
+        let acc = Vector3::new(0.0, 0.6, -0.8);
+        sim.set_accel(distorted(acc) - Vector3::new(0.1, 0.0, 0.0));
+        sim.sample();
+        let mut samples = [crate::Sample::default(); 1];
+        mpu.drain_fifo(&mut samples).unwrap();
+        assert!((samples[0].accel.unwrap() - acc).amax() < 2e-3);
+
+        // the persisted calibration restores the registers after a power cycle
+        let blob = mpu.calibration().unwrap().encode();
+        mpu.set_accel_offset(AccelOffset(Vector3::new(102, 0, 0))).unwrap();
+        mpu.apply_calibration(&Calibration::decode(&blob).unwrap()).unwrap();
+        assert_eq!(mpu.get_accel_offset().unwrap(), offset);
+        sim.sample();
+        mpu.drain_fifo(&mut samples).unwrap();
+        assert!((samples[0].accel.unwrap() - acc).amax() < 2e-3);
+    }
+
+    #[test]
+    fn calibration_blob() {
+        let mut six = SixPositionCalibration::new();
+        for face in Face::ALL {
+            six.add(face, distorted(face.gravity()));
+        }
+        let cal = Calibration {
+            gyro_offset: GyroOffset(Vector3::new(-12, 40, 3)),
+            temp: TempCoefficients {
+                reference: 31.5,
+                gyro: [Vector3::new(1e-4, -2e-4, 3e-5), Vector3::new(1e-6, 0.0, -1e-6)],
+                accel: [Vector3::new(2e-4, 0.0, -1e-4), Vector3::zeros()],
+            },
+            mounting: UnitQuaternion::from_euler_angles(0.0, 0.0, PI_180 * 90.0),
+            accel_offset_registers: Some(AccelOffset(Vector3::new(512, -3, 0))),
+            ..six.solve().unwrap()
+        };
+
+        let blob = cal.encode();
+        assert_eq!(blob.len(), CALIBRATION_BLOB_SIZE);
+        assert_eq!(&blob[..4], b"M6\x01\x01");
+        assert_eq!(&blob[4..6], &[0xf4, 0xff]);
+        let decoded = Calibration::decode(&blob).unwrap();
+        assert_eq!(decoded.gyro_offset, cal.gyro_offset);
+        assert_eq!(decoded.accel_misalignment, cal.accel_misalignment);
+        assert_eq!((decoded.accel_offset, decoded.accel_scale, decoded.temp), (cal.accel_offset, cal.accel_scale, cal.temp));
+        assert!(decoded.mounting.angle_to(&cal.mounting) < 1e-6);
+        assert_eq!(decoded.accel_offset_registers, cal.accel_offset_registers);
+        assert_eq!(&blob[138..140], &[0x00, 0x02]);
+        assert_eq!(Calibration::decode(&Calibration::default().encode()).unwrap(), Calibration::default());
+
+        let mut corrupted = blob;
+        corrupted[50] ^= 0x04;
+        assert!(matches!(Calibration::decode(&corrupted), Err(SensorError::CalibrationChecksum)));
+        let mut future = blob;
+        future[2] = CALIBRATION_VERSION + 1;
+        assert!(matches!(Calibration::decode(&future), Err(SensorError::InvalidCalibrationBlob)));
+        assert!(matches!(Calibration::decode(&[0xff; CALIBRATION_BLOB_SIZE]), Err(SensorError::InvalidCalibrationBlob)));
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn apply_calibration() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        let cal = Calibration {
+            gyro_offset: GyroOffset::from_rad_s(Vector3::new(-0.1, 0.0, 0.0)),
+            accel_offset: Vector3::new(0.0, 0.0, 0.1),
+            // sensor z axis points along device x
+            mounting: UnitQuaternion::from_euler_angles(0.0, PI_180 * 90.0, 0.0),
+            ..Calibration::default()
+        };
+        let blob = cal.encode();
+
+        mpu.apply_calibration(&Calibration::decode(&blob).unwrap()).unwrap();
+        assert_eq!(mpu.get_gyro_offset().unwrap(), cal.gyro_offset);
+        // without pushed registers the factory trim stays
+        assert_eq!(mpu.get_accel_offset().unwrap(), AccelOffset::default());
+        sim.push_sample(Vector3::new(0.0, 0.0, 1.1), Vector3::new(0.1, 0.0, 0.2), TEMP_OFFSET);
+        assert!((mpu.get_acc().unwrap() - Vector3::new(1.0, 0.0, 0.0)).amax() < 1e-3);
+        assert!((mpu.get_gyro().unwrap() - Vector3::new(0.2, 0.0, 0.0)).amax() < 2e-3);
+        let all = mpu.read_all().unwrap();
+        assert!((all.gyro - Vector3::new(0.2, 0.0, 0.0)).amax() < 2e-3);
+
+        // gyro calibration updates the calibration
+        let mut delay = sim.sampling_delay(|_, _| {});
+        let offset = mpu.calibrate_gyro(&mut delay, 10).unwrap();
+        assert_eq!(mpu.calibration().unwrap().gyro_offset, offset);
+        assert_eq!(mpu.calibration().unwrap().accel_offset, cal.accel_offset);
+    }
+
+    /// Drift of the simulated sensor, 0 at 25 °C
+    fn gyro_drift(temp: f32) -> Vector3<f32> {
+        let dt = temp - 25.0;
+        Vector3::new(1e-3, -5e-4, 0.0) * dt + Vector3::new(2e-5, 0.0, -1e-5) * (dt * dt)
+    }
+
+    fn accel_drift(temp: f32) -> Vector3<f32> {
+        Vector3::new(0.0, 2e-4, -3e-4) * (temp - 25.0)
+    }
+
+    #[test]
+    fn temp_drift_fit() {
+        let mut recorder = TempDriftRecorder::new();
+        assert!(matches!(recorder.fit(25.0), Err(SensorError::InsufficientTempRange)));
+        for t in [30.0, 30.2, 30.4] {
+            recorder.push(t, gyro_drift(t), Vector3::z() + accel_drift(t));
+        }
+        assert!(matches!(recorder.fit(30.0), Err(SensorError::InsufficientTempRange)));
+
+        for i in 0..20 {
+            let t = 25.0 + i as f32;
+            recorder.push(t, gyro_drift(t), Vector3::z() + accel_drift(t));
+        }
+        assert_eq!((recorder.len(), recorder.temp_range()), (23, Some((25.0, 44.0))));
+        let coefficients = recorder.fit(25.0).unwrap();
+        for t in [20.0, 25.0, 37.3, 50.0] {
+            assert!((coefficients.gyro_drift(t) - gyro_drift(t)).amax() < 1e-5);
+            assert!((coefficients.accel_drift(t) - accel_drift(t)).amax() < 1e-5);
+        }
+        // drift relative to another reference
+        let coefficients = recorder.fit(35.0).unwrap();
+        assert!((coefficients.gyro_drift(40.0) - (gyro_drift(40.0) - gyro_drift(35.0))).amax() < 1e-5);
+        assert!(coefficients.has_drift() && !TempCoefficients::default().has_drift());
+    }
+
+    #[test]
+    fn temp_compensation() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        let accel = Vector3::new(0.0, 0.0, 1.0);
+        // warm-up in steps of 2 °C, 10 samples each
+        let mut delay = sim.sampling_delay(|sim, n| {
+            let temp = 25.0 + (n / 10) as f32 * 2.0;
+            sim.push_sample(accel + accel_drift(temp), gyro_drift(temp), temp);
+        });
+
+        let mut recorder = TempDriftRecorder::new();
+        for _ in 0..10 {
+            mpu.record_temp_drift(&mut delay, &mut recorder, 10).unwrap();
+        }
+        mpu.set_calibration(Some(Calibration { temp: recorder.fit(25.0).unwrap(), ..Calibration::default() }));
+
+        let temp = 45.0;
+        mpu.enable_fifo(true, true).unwrap();
+        sim.push_sample(accel + accel_drift(temp), gyro_drift(temp), temp);
+        assert!((mpu.get_gyro().unwrap()).amax() < 5e-4);
+        assert!((mpu.get_acc().unwrap() - accel).amax() < 1e-3);
+        let all = mpu.read_all().unwrap();
+        assert!(all.gyro.amax() < 5e-4 && (all.accel - accel).amax() < 1e-3);
+        let fifo = mpu.read_fifo().unwrap();
+        assert!((fifo[1] * PI_180).amax() < 5e-4 && (fifo[0] - accel).amax() < 1e-3);
+
+        // accel only packets use the current temperature
+        mpu.enable_fifo(true, false).unwrap();
+        sim.sample();
+        let mut samples = [crate::Sample::default(); 1];
+        mpu.drain_fifo(&mut samples).unwrap();
+        assert!((samples[0].accel.unwrap() - accel).amax() < 1e-3);
+    }
+
+    #[test]
+    fn calibrate_gyro_rejects_motion() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        let mut delay = sim.sampling_delay(|sim, n| sim.set_gyro(Vector3::new(0.0, 0.0, n as f32 * 0.01)));
+
+        assert!(matches!(mpu.calibrate_gyro(&mut delay, 20),
+                         Err(Mpu6886Error::SensorError(SensorError::MotionDuringCalibration))));
+        assert_eq!(mpu.get_gyro_offset().unwrap(), GyroOffset::default());
+        assert!(matches!(mpu.calibrate_gyro(&mut delay, 1),
+                         Err(Mpu6886Error::SensorError(SensorError::NotEnoughSamples))));
+        // slow drift passes a generous threshold
+        assert!(mpu.calibrate_gyro_with_threshold(&mut delay, 20, 0.1).is_ok());
+    }
+}
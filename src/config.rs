@@ -1,32 +1,49 @@
-
-
+use crate::bits;
+use crate::device::*;
 use crate::error::*;
 
-pub(crate) trait Bitfield {
-    const BITMASK: u8;
+use core::ops::{BitAnd, BitOr, Not};
+#[cfg(feature = "float")]
+use nalgebra::Vector3;
 
+pub(crate) trait Bitfield {
     /// Bit value of a discriminant, shifted to the correct position if
     /// necessary
     fn bits(self) -> u8;
 }
 /// Accelareration Filter Bandwith selection values
-#[derive(Clone, Copy, Debug, PartialEq)]
+///
+/// Discriminant is `ACCEL_FCHOICE_B` (bit 3) and `A_DLPF_CFG` (bits 2:0) of ACCEL_CONFIG_2.
+/// ```text
+/// ACCEL_FCHOICE_B  A_DLPF_CFG  3-DB BW (HZ)  NOISE BW (HZ)  RATE (KHZ)
+/// 1                X           1046.0        1100.0         4
+/// 0                0           218.1         235.0          1
+/// 0                1           218.1         235.0          1
+/// 0                2           99.0          121.3          1
+/// 0                3           44.8          61.5           1
+/// 0                4           21.2          31.0           1
+/// 0                5           10.2          15.5           1
+/// 0                6           5.1           7.8            1
+/// 0                7           420.0         441.6          1
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum AccelBw {
-    /// BW filter bypassed
+    /// 1046 Hz, BW filter bypassed, 4 kHz rate
     Hz1046  = 0b1000,
-    /// 180 Hz
+    /// 218.1 Hz
+    #[default]
     Hz218 = 0b0000,
-    /// 121 Hz
+    /// 99 Hz
     Hz99 = 0b0010,
-    /// 73 Hz
+    /// 44.8 Hz
     Hz45 = 0b0011,
-    /// 53 Hz
+    /// 21.2 Hz
     Hz21 = 0b0100,
-    /// 34 Hz
+    /// 10.2 Hz
     Hz10 = 0b0101,
-    /// 25 Hz
+    /// 5.1 Hz
     Hz5 = 0b0110,
-    /// 16 Hz
+    /// 420 Hz
     Hz420 = 0b0111,
 }
 
@@ -38,7 +55,7 @@ impl AccelBw {
         match self {
             Hz1046 => 1046.0, // filter is bypassed
             Hz218 => 218.1,
-            Hz99 => 121.0,
+            Hz99 => 99.0,
             Hz45 => 44.8,
             Hz21 => 21.2,
             Hz10 => 10.2,
@@ -46,17 +63,25 @@ impl AccelBw {
             Hz420 => 420.0,
         }
     }
-}
 
-impl Default for AccelBw {
-    fn default() -> Self {
-        Self::Hz218
+    /// Equivalent noise bandwidth in Hz, the white noise passed by the filter
+    pub fn noise_bandwidth(self) -> f32 {
+        use AccelBw::*;
+
+        match self {
+            Hz1046 => 1100.0,
+            Hz218 => 235.0,
+            Hz99 => 121.3,
+            Hz45 => 61.5,
+            Hz21 => 31.0,
+            Hz10 => 15.5,
+            Hz5 => 7.8,
+            Hz420 => 441.6,
+        }
     }
 }
 
 impl Bitfield for AccelBw {
-    const BITMASK: u8 = 0b0000_1111;
-
     fn bits(self) -> u8 {
         // `A_DLPF_CFG` occupies bits 2:0 in the register
         // ACCEL_FCHOICE_B  occupies bit 3
@@ -71,8 +96,8 @@ impl TryFrom<u8> for AccelBw {
         use AccelBw::*;
 
         match value {
-            0b1000 => Ok(Hz1046), // filter is bypassed
-            0b0000 => Ok(Hz218),
+            0b1000..=0b1111 => Ok(Hz1046), // filter is bypassed, A_DLPF_CFG don't care
+            0b0000 | 0b0001 => Ok(Hz218),
             0b0010 => Ok(Hz99),
             0b0011 => Ok(Hz45),
             0b0100 => Ok(Hz21),
@@ -84,29 +109,59 @@ impl TryFrom<u8> for AccelBw {
     }
 }
 
+/// Accelerometer averaging filter (`DEC2_CFG` of ACCEL_CONFIG_2), used in low power mode
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AccelAveraging {
+    /// Average 4 samples
+    #[default]
+    X4 = 0,
+    /// Average 8 samples
+    X8,
+    /// Average 16 samples
+    X16,
+    /// Average 32 samples
+    X32,
+}
 
-/// Accelareration Filter Bandwith selection values
-#[derive(Clone, Copy, Debug, PartialEq)]
+impl From<u8> for AccelAveraging {
+    fn from(avg: u8) -> Self {
+        match avg {
+            0 => AccelAveraging::X4,
+            1 => AccelAveraging::X8,
+            2 => AccelAveraging::X16,
+            3 => AccelAveraging::X32,
+            _ => AccelAveraging::X4,
+        }
+    }
+}
+
+
+/// Gyro Filter Bandwith selection values
+///
+/// Discriminant is `FCHOICE_B` (bits 4:3, GYRO_CONFIG bits 1:0) and `DLPF_CFG`
+/// (bits 2:0, CONFIG bits 2:0), see [`GYRO_CONFIG::FCHOICE_B`](crate::device::GYRO_CONFIG::FCHOICE_B)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum GyroBw {
-    /// BW filter bypassed
+    /// 8173 Hz, BW filter bypassed (FCHOICE_B = x1), 32 kHz rate
     Hz8173  = 0b01000,
-    /// BW filter bypassed
-    // Hz3182  = 0b10000,
-    /// 180 Hz
+    /// 3281 Hz, BW filter bypassed (FCHOICE_B = 10), 32 kHz rate
+    Hz3281Bypass  = 0b10000,
+    /// 250 Hz, 8 kHz rate
     Hz250 = 0b00000,
-    /// 180 Hz
+    /// 176 Hz
     Hz176 = 0b00001,
-    /// 121 Hz
+    /// 92 Hz
     Hz92 = 0b00010,
-    /// 73 Hz
+    /// 41 Hz
     Hz41 = 0b00011,
-    /// 53 Hz
+    /// 20 Hz
     Hz20 = 0b00100,
-    /// 34 Hz
+    /// 10 Hz
     Hz10 = 0b00101,
-    /// 25 Hz
+    /// 5 Hz
     Hz5 = 0b00110,
-    /// 16 Hz
+    /// 3281 Hz, 8 kHz rate
+    #[default]
     Hz3281 = 0b00111,
 }
 
@@ -117,7 +172,7 @@ impl GyroBw {
 
         match self {
             Hz8173 => 8173.0, // filter is bypassed
-            // Hz3182 => 3182.0,
+            Hz3281Bypass => 3281.0, // filter is bypassed
             Hz250 => 250.0,
             Hz176 => 176.0,
             Hz92 => 92.0,
@@ -128,20 +183,45 @@ impl GyroBw {
             Hz3281 => 3281.0,
         }
     }
-}
 
-impl Default for GyroBw {
-    fn default() -> Self {
-        Self::Hz3281
+    /// Equivalent noise bandwidth in Hz, the white noise passed by the filter
+    pub fn noise_bandwidth(self) -> f32 {
+        use GyroBw::*;
+
+        match self {
+            Hz8173 => 8595.1,
+            Hz3281Bypass => 3451.0,
+            Hz250 => 306.6,
+            Hz176 => 177.0,
+            Hz92 => 108.6,
+            Hz41 => 59.0,
+            Hz20 => 30.5,
+            Hz10 => 15.6,
+            Hz5 => 8.0,
+            Hz3281 => 3451.0,
+        }
+    }
+
+    /// `DLPF_CFG` of CONFIG
+    pub(crate) fn dlpf_cfg(self) -> u8 {
+        self.bits() & 0b111
+    }
+
+    /// `FCHOICE_B` of GYRO_CONFIG
+    pub(crate) fn fchoice_b(self) -> u8 {
+        self.bits() >> 3
+    }
+
+    /// Bandwidth of `DLPF_CFG` and `FCHOICE_B`, read from CONFIG and GYRO_CONFIG
+    pub(crate) fn from_fields(dlpf_cfg: u8, fchoice_b: u8) -> Result<Self, SensorError> {
+        Self::try_from(dlpf_cfg | (fchoice_b << 3))
     }
 }
 
 impl Bitfield for GyroBw {
-    const BITMASK: u8 = 0b0001_1111;
-
     fn bits(self) -> u8 {
-        // `A_DLPF_CFG` occupies bits 2:0 in the register
-        // ACCEL_FCHOICE_B  occupies bit 3
+        // `DLPF_CFG` occupies bits 2:0 in CONFIG
+        // `FCHOICE_B` occupies bits 1:0 in GYRO_CONFIG, here bits 4:3
         self as u8
     }
 }
@@ -152,16 +232,444 @@ impl TryFrom<u8> for GyroBw {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         use GyroBw::*;
 
-        match value {
-            0b10000 => Ok(Hz8173), // filter is bypassed
-            0b00000 => Ok(Hz250),
-            0b00010 => Ok(Hz92),
-            0b00011 => Ok(Hz41),
-            0b00100 => Ok(Hz20),
-            0b00101 => Ok(Hz10),
-            0b00110 => Ok(Hz5),
-            0b00111 => Ok(Hz3281),
+        // FCHOICE_B != 0 bypasses the filter, DLPF_CFG don't care
+        match (value >> 3, value & 0b111) {
+            (0b01 | 0b11, _) => Ok(Hz8173),
+            (0b10, _) => Ok(Hz3281Bypass),
+            (0b00, 0) => Ok(Hz250),
+            (0b00, 1) => Ok(Hz176),
+            (0b00, 2) => Ok(Hz92),
+            (0b00, 3) => Ok(Hz41),
+            (0b00, 4) => Ok(Hz20),
+            (0b00, 5) => Ok(Hz10),
+            (0b00, 6) => Ok(Hz5),
+            (0b00, 7) => Ok(Hz3281),
             _ => Err(SensorError::InvalidDiscriminant),
         }
     }
 }
+
+/// Sample the wake on motion logic compares the current sample to (`ACCEL_INTEL_MODE`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WomMode {
+    /// Compare with the previous sample
+    #[default]
+    Previous,
+    /// Compare with the initial sample, taken when wake on motion is enabled
+    ///
+    /// This is the MPU6500 meaning of `ACCEL_INTEL_MODE` = 0. The MPU6886 register map documents
+    /// 0 as not used, so the behaviour of the MPU6886 in this mode is unspecified, verify it on
+    /// the board or use `Previous`.
+    Initial,
+}
+
+/// How the per axis thresholds are combined (`WOM_TH_MODE`)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WomLogic {
+    /// Motion on any axis exceeding its threshold
+    #[default]
+    Or,
+    /// Motion on all axes exceeding their thresholds
+    And,
+}
+
+/// Behaviour of the interrupt pin (`LATCH_INT_EN` of INT_PIN_CFG)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InterruptLatch {
+    /// 50 us pulse
+    #[default]
+    Pulsed,
+    /// Level held until the interrupt status is cleared
+    Latched,
+}
+
+/// Wake on motion configuration, see `Mpu6886::setup_wake_on_motion`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WomConfig {
+    /// Thresholds of the x, y and z axis in mg, 0 to 1020 mg in steps of 4 mg
+    pub threshold_mg: [u16; 3],
+    /// Reference sample
+    pub mode: WomMode,
+    /// Combination of the axes
+    pub logic: WomLogic,
+    /// Interrupt pin behaviour
+    pub latch: InterruptLatch,
+}
+
+impl Default for WomConfig {
+    fn default() -> Self {
+        WomConfig {
+            threshold_mg: [160; 3],
+            mode: WomMode::default(),
+            logic: WomLogic::default(),
+            latch: InterruptLatch::Latched,
+        }
+    }
+}
+
+/// Axes that detected motion, decoded from the WOM bits of INT_STATUS
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WomStatus {
+    /// Motion on x axis
+    pub x: bool,
+    /// Motion on y axis
+    pub y: bool,
+    /// Motion on z axis
+    pub z: bool,
+}
+
+impl WomStatus {
+    /// Motion detected on at least one axis
+    pub fn any(&self) -> bool {
+        self.x || self.y || self.z
+    }
+}
+
+impl From<u8> for WomStatus {
+    /// Decodes an INT_STATUS value
+    fn from(int_status: u8) -> Self {
+        WomStatus {
+            x: bits::get_bit(int_status, INT_STATUS::WOM_X_INT) != 0,
+            y: bits::get_bit(int_status, INT_STATUS::WOM_Y_INT) != 0,
+            z: bits::get_bit(int_status, INT_STATUS::WOM_Z_INT) != 0,
+        }
+    }
+}
+
+/// Set of enabled accel and gyro axes, the inverted standby bits of PWR_MGMT_2
+///
+/// Combine with `|`, e.g. `Axes::ACCEL | Axes::GYRO_Z`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Axes(u8);
+
+impl Axes {
+    /// accel x axis
+    pub const ACCEL_X: Axes = Axes(1 << PWR_MGMT_2::STBY_XA);
+    /// accel y axis
+    pub const ACCEL_Y: Axes = Axes(1 << PWR_MGMT_2::STBY_YA);
+    /// accel z axis
+    pub const ACCEL_Z: Axes = Axes(1 << PWR_MGMT_2::STBY_ZA);
+    /// gyro x axis
+    pub const GYRO_X: Axes = Axes(1 << PWR_MGMT_2::STBY_XG);
+    /// gyro y axis
+    pub const GYRO_Y: Axes = Axes(1 << PWR_MGMT_2::STBY_YG);
+    /// gyro z axis
+    pub const GYRO_Z: Axes = Axes(1 << PWR_MGMT_2::STBY_ZG);
+    /// all accel axes
+    pub const ACCEL: Axes = Axes(Self::ACCEL_X.0 | Self::ACCEL_Y.0 | Self::ACCEL_Z.0);
+    /// all gyro axes
+    pub const GYRO: Axes = Axes(Self::GYRO_X.0 | Self::GYRO_Y.0 | Self::GYRO_Z.0);
+    /// all axes, reset state
+    pub const ALL: Axes = Axes(Self::ACCEL.0 | Self::GYRO.0);
+    /// no axis
+    pub const NONE: Axes = Axes(0);
+
+    /// Axes from the bits of PWR_MGMT_2 (set bit = axis in standby), reserved bits are ignored
+    pub fn from_standby_bits(pwr_mgmt_2: u8) -> Self {
+        Axes(!pwr_mgmt_2 & Self::ALL.0)
+    }
+
+    /// Standby bits of PWR_MGMT_2 for these axes
+    pub fn standby_bits(self) -> u8 {
+        !self.0 & Self::ALL.0
+    }
+
+    /// true if all axes of other are in self
+    pub fn contains(self, other: Axes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[cfg(feature = "float")]
+    /// Accel reading with disabled axes set to None
+    pub(crate) fn mask_accel(self, acc: Vector3<f32>) -> Vector3<Option<f32>> {
+        Self::mask(acc, [self.contains(Self::ACCEL_X), self.contains(Self::ACCEL_Y), self.contains(Self::ACCEL_Z)])
+    }
+
+    #[cfg(feature = "float")]
+    /// Gyro reading with disabled axes set to None
+    pub(crate) fn mask_gyro(self, gyro: Vector3<f32>) -> Vector3<Option<f32>> {
+        Self::mask(gyro, [self.contains(Self::GYRO_X), self.contains(Self::GYRO_Y), self.contains(Self::GYRO_Z)])
+    }
+
+    #[cfg(feature = "float")]
+    fn mask(v: Vector3<f32>, enabled: [bool; 3]) -> Vector3<Option<f32>> {
+        Vector3::new(
+            enabled[0].then_some(v.x),
+            enabled[1].then_some(v.y),
+            enabled[2].then_some(v.z),
+        )
+    }
+}
+
+impl Default for Axes {
+    fn default() -> Self {
+        Axes::ALL
+    }
+}
+
+impl BitOr for Axes {
+    type Output = Axes;
+
+    fn bitor(self, rhs: Axes) -> Axes {
+        Axes(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Axes {
+    type Output = Axes;
+
+    fn bitand(self, rhs: Axes) -> Axes {
+        Axes(self.0 & rhs.0)
+    }
+}
+
+impl Not for Axes {
+    type Output = Axes;
+
+    fn not(self) -> Axes {
+        Axes(!self.0 & Self::ALL.0)
+    }
+}
+
+/// Interrupt pin configuration (INT_PIN_CFG), built from the reset state:
+///
+/// ```
+/// # use mpu6886::config::*;
+/// let pin = InterruptPinConfig::default().active_low(true).latch(InterruptLatch::Latched);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InterruptPinConfig {
+    active_low: bool,
+    open_drain: bool,
+    latch: InterruptLatch,
+    clear_on_any_read: bool,
+}
+
+impl InterruptPinConfig {
+    /// Pin is active low instead of active high (`INT_LEVEL`)
+    pub fn active_low(mut self, active_low: bool) -> Self {
+        self.active_low = active_low;
+        self
+    }
+
+    /// Pin is open drain instead of push-pull (`INT_OPEN`)
+    pub fn open_drain(mut self, open_drain: bool) -> Self {
+        self.open_drain = open_drain;
+        self
+    }
+
+    /// Pulse or level until cleared (`LATCH_INT_EN`)
+    pub fn latch(mut self, latch: InterruptLatch) -> Self {
+        self.latch = latch;
+        self
+    }
+
+    /// Status is cleared by any register read instead of reading INT_STATUS only (`INT_RD_CLEAR`)
+    pub fn clear_on_any_read(mut self, clear_on_any_read: bool) -> Self {
+        self.clear_on_any_read = clear_on_any_read;
+        self
+    }
+}
+
+impl Bitfield for InterruptPinConfig {
+    fn bits(self) -> u8 {
+        let mut byte = 0;
+        bits::set_bit(&mut byte, INT_PIN_CFG::INT_LEVEL, self.active_low);
+        bits::set_bit(&mut byte, INT_PIN_CFG::INT_OPEN, self.open_drain);
+        bits::set_bit(&mut byte, INT_PIN_CFG::LATCH_INT_EN, self.latch == InterruptLatch::Latched);
+        bits::set_bit(&mut byte, INT_PIN_CFG::INT_RD_CLEAR, self.clear_on_any_read);
+        byte
+    }
+}
+
+impl From<u8> for InterruptPinConfig {
+    /// Decodes an INT_PIN_CFG value
+    fn from(int_pin_cfg: u8) -> Self {
+        InterruptPinConfig {
+            active_low: bits::get_bit(int_pin_cfg, INT_PIN_CFG::INT_LEVEL) != 0,
+            open_drain: bits::get_bit(int_pin_cfg, INT_PIN_CFG::INT_OPEN) != 0,
+            latch: if bits::get_bit(int_pin_cfg, INT_PIN_CFG::LATCH_INT_EN) != 0 {
+                InterruptLatch::Latched
+            } else {
+                InterruptLatch::Pulsed
+            },
+            clear_on_any_read: bits::get_bit(int_pin_cfg, INT_PIN_CFG::INT_RD_CLEAR) != 0,
+        }
+    }
+}
+
+/// Set of interrupt sources, the bits of INT_ENABLE
+///
+/// Combine with `|`, e.g. `Interrupts::WOM | Interrupts::FIFO_OVERFLOW`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Interrupts(u8);
+
+impl Interrupts {
+    /// wake on motion, accel x axis
+    pub const WOM_X: Interrupts = Interrupts(1 << INT_ENABLE::WOM_X_INT_EN);
+    /// wake on motion, accel y axis
+    pub const WOM_Y: Interrupts = Interrupts(1 << INT_ENABLE::WOM_Y_INT_EN);
+    /// wake on motion, accel z axis
+    pub const WOM_Z: Interrupts = Interrupts(1 << INT_ENABLE::WOM_Z_INT_EN);
+    /// wake on motion, all axes
+    pub const WOM: Interrupts = Interrupts(Self::WOM_X.0 | Self::WOM_Y.0 | Self::WOM_Z.0);
+    /// FIFO overflow
+    pub const FIFO_OVERFLOW: Interrupts = Interrupts(1 << INT_ENABLE::FIFO_OFLOW_END);
+    /// gyroscope drive system ready
+    pub const GDRIVE: Interrupts = Interrupts(1 << INT_ENABLE::GDRIVE_INT_EN);
+    /// new sample in the data registers
+    pub const DATA_READY: Interrupts = Interrupts(1 << INT_ENABLE::DATA_RDY_EN);
+    /// all interrupt sources
+    pub const ALL: Interrupts = Interrupts(Self::WOM.0 | Self::FIFO_OVERFLOW.0 | Self::GDRIVE.0 | Self::DATA_READY.0);
+    /// no interrupt
+    pub const NONE: Interrupts = Interrupts(0);
+
+    /// Interrupts from an INT_ENABLE value, reserved bits are ignored
+    pub fn from_bits(int_enable: u8) -> Self {
+        Interrupts(int_enable & Self::ALL.0)
+    }
+
+    /// INT_ENABLE bits of these interrupts
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// true if all interrupts of other are in self
+    pub fn contains(self, other: Interrupts) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Interrupts {
+    type Output = Interrupts;
+
+    fn bitor(self, rhs: Interrupts) -> Interrupts {
+        Interrupts(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Interrupts {
+    type Output = Interrupts;
+
+    fn bitand(self, rhs: Interrupts) -> Interrupts {
+        Interrupts(self.0 & rhs.0)
+    }
+}
+
+impl Not for Interrupts {
+    type Output = Interrupts;
+
+    fn not(self) -> Interrupts {
+        Interrupts(!self.0 & Self::ALL.0)
+    }
+}
+
+/// Pending interrupts, decoded from a single read of INT_STATUS
+///
+/// Reading INT_STATUS clears it, so all sources are taken from the same read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InterruptStatus {
+    /// Wake on motion per axis
+    pub wom: WomStatus,
+    /// FIFO overflowed, data was lost
+    pub fifo_overflow: bool,
+    /// Gyroscope drive system ready
+    pub gdrive: bool,
+    /// New sample in the data registers
+    pub data_ready: bool,
+}
+
+impl From<u8> for InterruptStatus {
+    /// Decodes an INT_STATUS value
+    fn from(int_status: u8) -> Self {
+        InterruptStatus {
+            wom: WomStatus::from(int_status),
+            fifo_overflow: bits::get_bit(int_status, INT_STATUS::FIFO_OFLOW_INT) != 0,
+            gdrive: bits::get_bit(int_status, INT_STATUS::GDRIVE_INT) != 0,
+            data_ready: bits::get_bit(int_status, INT_STATUS::DATA_RDY_INT) != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accel_bw_round_trip() {
+        use AccelBw::*;
+
+        for bw in [Hz1046, Hz218, Hz99, Hz45, Hz21, Hz10, Hz5, Hz420] {
+            assert_eq!(AccelBw::try_from(bw.bits()).unwrap(), bw);
+        }
+        assert_eq!(AccelBw::try_from(0b0001).unwrap(), Hz218);
+        assert_eq!(AccelBw::try_from(0b1101).unwrap(), Hz1046);
+        assert!(AccelBw::try_from(0b1_0000).is_err());
+    }
+
+    #[test]
+    fn gyro_bw_round_trip() {
+        use GyroBw::*;
+
+        for bw in [Hz8173, Hz3281Bypass, Hz250, Hz176, Hz92, Hz41, Hz20, Hz10, Hz5, Hz3281] {
+            assert_eq!(GyroBw::try_from(bw.bits()).unwrap(), bw);
+        }
+        assert_eq!(GyroBw::try_from(0b11_010).unwrap(), Hz8173);
+        assert_eq!(GyroBw::try_from(0b10_011).unwrap(), Hz3281Bypass);
+        assert!(GyroBw::try_from(0b100_000).is_err());
+    }
+
+    #[test]
+    fn axes() {
+        assert_eq!(Axes::ALL.standby_bits(), 0);
+        assert_eq!(Axes::ACCEL.standby_bits(), 0b0000_0111);
+        assert_eq!((Axes::GYRO | Axes::ACCEL_Y).standby_bits(), 0b0010_1000);
+        assert_eq!(Axes::from_standby_bits(0b1100_0111), Axes::ACCEL);
+        assert_eq!(!Axes::GYRO_X, Axes::ACCEL | Axes::GYRO_Y | Axes::GYRO_Z);
+        assert!(Axes::ALL.contains(Axes::GYRO));
+        assert!(!Axes::ACCEL.contains(Axes::ACCEL_X | Axes::GYRO_X));
+        assert_eq!(Axes::ACCEL & Axes::ACCEL_Z, Axes::ACCEL_Z);
+    }
+
+    #[cfg(feature = "float")]
+    #[test]
+    fn axes_mask() {
+        assert_eq!((Axes::ACCEL_X | Axes::GYRO_Z).mask_accel(Vector3::new(1.0, 2.0, 3.0)),
+                   Vector3::new(Some(1.0), None, None));
+    }
+
+    #[test]
+    fn interrupt_pin_config() {
+        let pin = InterruptPinConfig::default()
+            .active_low(true)
+            .open_drain(true)
+            .latch(InterruptLatch::Latched)
+            .clear_on_any_read(true);
+        assert_eq!(pin.bits(), 0xf0);
+        assert_eq!(InterruptPinConfig::from(0xfc), pin);
+        assert_eq!(InterruptPinConfig::default().latch(InterruptLatch::Latched).bits(), 0x20);
+        assert_eq!(InterruptPinConfig::from(0x0c), InterruptPinConfig::default());
+    }
+
+    #[test]
+    fn interrupt_status() {
+        assert_eq!(InterruptStatus::from(0xff), InterruptStatus {
+            wom: WomStatus { x: true, y: true, z: true },
+            fifo_overflow: true,
+            gdrive: true,
+            data_ready: true,
+        });
+        let status = InterruptStatus::from(0b0001_0001);
+        assert!(status.fifo_overflow && status.data_ready && !status.gdrive && !status.wom.any());
+        assert_eq!(Interrupts::from_bits(0xff), Interrupts::ALL);
+        assert_eq!((Interrupts::WOM | Interrupts::DATA_READY).bits(), 0xe1);
+        assert_eq!(!Interrupts::WOM, Interrupts::FIFO_OVERFLOW | Interrupts::GDRIVE | Interrupts::DATA_READY);
+    }
+
+    #[test]
+    fn wom_status() {
+        assert_eq!(WomStatus::from(0b0100_0001), WomStatus { x: false, y: true, z: false });
+        assert_eq!(WomStatus::from(0b1010_0000), WomStatus { x: true, y: false, z: true });
+        assert!(!WomStatus::from(0b0001_0101).any());
+    }
+}
@@ -33,15 +33,62 @@ pub const GYRO_SENS: (f32, f32, f32, f32) = (131., 65.5, 32.8, 16.4);
 ///     * https://github.com/kriswiner/MPU6050/wiki/2014-Invensense-Developer%27s-Conference
 ///     * rust MPU9250 driver on github
 pub const ACCEL_SENS: (f32, f32, f32, f32) = (16384., 8192., 4096., 2048.);
+/// Gyro rate noise spectral density in dps/√Hz (datasheet typical)
+pub const GYRO_NOISE_DENSITY: f32 = 0.004;
+/// Accelerometer noise power spectral density in g/√Hz (datasheet typical, 100 µg/√Hz)
+pub const ACCEL_NOISE_DENSITY: f32 = 100e-6;
 /// Temperature Offset
 pub const TEMP_OFFSET: f32 = 25.0;
 /// Temperature Sensitivity
 pub const TEMP_SENSITIVITY: f32 = 326.8;
 
 /// Motion Threshold Register
+#[deprecated(note = "mpu6050 register, reserved on the mpu6886, see `ACCEL_WOM_X_THR`")]
 pub const MOT_THR: u8 = 0x1F;
 /// Motion Duration Detection Register
+#[deprecated(note = "mpu6050 register, on the mpu6886 this is `ACCEL_WOM_X_THR`")]
 pub const MOT_DUR: u8 = 0x20;
+/// Wake on motion threshold of accel x axis
+pub const ACCEL_WOM_X_THR: u8 = 0x20;
+/// Wake on motion threshold of accel y axis
+pub const ACCEL_WOM_Y_THR: u8 = 0x21;
+/// Wake on motion threshold of accel z axis
+pub const ACCEL_WOM_Z_THR: u8 = 0x22;
+/// LSB of the wake on motion thresholds in mg, 0 to 1020 mg
+pub const WOM_THR_LSB_MG: u16 = 4;
+/// Gyro x self-test factory trim code
+///
+/// The read-only codes SELF_TEST_X/Y/Z_GYRO and SELF_TEST_X/Y/Z_ACCEL hold the self-test
+/// response measured in production, at ±250 dps and ±2 g. A code c stands for a response of
+/// 2620 * 1.01^(c - 1) LSB, 0 means not programmed.
+pub const SELF_TEST_X_GYRO: u8 = 0x00;
+/// Gyro y self-test factory trim code
+pub const SELF_TEST_Y_GYRO: u8 = 0x01;
+/// Gyro z self-test factory trim code
+pub const SELF_TEST_Z_GYRO: u8 = 0x02;
+/// Accel x self-test factory trim code
+pub const SELF_TEST_X_ACCEL: u8 = 0x0d;
+/// Accel y self-test factory trim code
+pub const SELF_TEST_Y_ACCEL: u8 = 0x0e;
+/// Accel z self-test factory trim code
+pub const SELF_TEST_Z_ACCEL: u8 = 0x0f;
+/// Gyro x user offset high byte
+///
+/// The 16 bit user offsets XG_OFFS_USR, YG_OFFS_USR and ZG_OFFS_USR are added to the gyro
+/// output in ±1000 dps scale (`GYRO_OFFSET_SENS`), independent of the gyro range.
+pub const XG_OFFS_USRH: u8 = 0x13;
+/// Gyro x user offset low byte
+pub const XG_OFFS_USRL: u8 = 0x14;
+/// Gyro y user offset high byte
+pub const YG_OFFS_USRH: u8 = 0x15;
+/// Gyro y user offset low byte
+pub const YG_OFFS_USRL: u8 = 0x16;
+/// Gyro z user offset high byte
+pub const ZG_OFFS_USRH: u8 = 0x17;
+/// Gyro z user offset low byte
+pub const ZG_OFFS_USRL: u8 = 0x18;
+/// Sensitivity of the gyro user offsets in LSB/dps
+pub const GYRO_OFFSET_SENS: f32 = 32.8;
 /// High Byte Register Gyro x orientation
 pub const GYRO_REGX_H: u8 = 0x43;
 /// High Byte Register Gyro y orientation
@@ -56,10 +103,39 @@ pub const ACC_REGY_H : u8= 0x3d;
 pub const ACC_REGZ_H : u8= 0x3f;
 /// High Byte Register Temperature
 pub const TEMP_OUT_H : u8= 0x41;
+/// Sample Rate Divider Register
+/// SAMPLE_RATE = INTERNAL_SAMPLE_RATE_MHZ / (1 + SMPLRT_DIV), where INTERNAL_SAMPLE_RATE_MHZ = 1 kHz.
+/// Only effective when FCHOICE_B is 2'b00 and 0 < DLPF_CFG < 7
+pub const SMPLRT_DIV: u8 = 0x19;
+/// Internal sample rate in mHz divided by SMPLRT_DIV
+pub const INTERNAL_SAMPLE_RATE_MHZ: u32 = 1_000_000;
+/// Accel sample rate in mHz with bypassed accel low pass filter (ACCEL_FCHOICE_B = 1)
+pub const ACCEL_BYPASS_SAMPLE_RATE_MHZ: u32 = 4_000_000;
 /// Slave address of mpu6886
 pub const DEFAULT_SLAVE_ADDR: u8 = 0x68;
 /// Internal register to check slave addr
 pub const WHOAMI: u8 = 0x75;
+/// WHOAMI value of the mpu6886
+pub const CHIP_ID: u8 = 0x19;
+/// Accel x offset cancellation bits 14:7
+///
+/// The 15 bit offsets XA_OFFSET, YA_OFFSET and ZA_OFFSET are stored in bits 7:0 of the high
+/// and bits 7:1 of the low register, bit 0 of the low register is reserved and must be kept.
+/// They hold factory trim values and are added to the accel output in steps of
+/// `ACCEL_OFFSET_LSB_MG`, independent of the accel range.
+pub const XA_OFFSET_H: u8 = 0x77;
+/// Accel x offset cancellation bits 6:0 in bits 7:1
+pub const XA_OFFSET_L: u8 = 0x78;
+/// Accel y offset cancellation bits 14:7
+pub const YA_OFFSET_H: u8 = 0x7a;
+/// Accel y offset cancellation bits 6:0 in bits 7:1
+pub const YA_OFFSET_L: u8 = 0x7b;
+/// Accel z offset cancellation bits 14:7
+pub const ZA_OFFSET_H: u8 = 0x7d;
+/// Accel z offset cancellation bits 6:0 in bits 7:1
+pub const ZA_OFFSET_L: u8 = 0x7e;
+/// Step of the accel offset registers in mg (0.98 mg, 2 LSB at ±16 g)
+pub const ACCEL_OFFSET_LSB_MG: f32 = 1000.0 / 1024.0;
 
 /// User control: FIFO enable general and reset
 /// bit 0 reset signal path
@@ -74,6 +150,14 @@ pub const FIFO_EN: u8 = 0x23;
 pub const FIFO_R_W: u8 = 0x74;
 /// FIFO counter address of high byte register
 pub const FIFO_COUNTH: u8 = 0x72;
+/// FIFO counter address of low byte register
+pub const FIFO_COUNTL: u8 = 0x73;
+/// FIFO watermark threshold in bytes, bits 9:8
+pub const FIFO_WM_TH1: u8 = 0x60;
+/// FIFO watermark threshold in bytes, bits 7:0
+pub const FIFO_WM_TH2: u8 = 0x61;
+/// FIFO size in bytes
+pub const FIFO_SIZE: u16 = 1024;
 
 /// Describes a bit block from bit number 'bit' to 'bit'+'length'
 pub struct BitBlock {
@@ -136,7 +220,8 @@ impl GYRO_CONFIG {
     /// Used to bypass DLPF as shown in Table 16 in datasheet.
     /// The DLPF is configured by DLPF_CFG, when FCHOICE_B [1:0] = 2b’00. The gyroscope and temperature sensor are
     ///filtered according to the value of DLPF_CFG and FCHOICE_B as shown in the table below.
-    /// 
+    ///
+    /// ```text
     ///                      | GYROSCOPE                | TEMPERATURE SENSOR
     /// FCHOICE_B  DLPF_CFG  3-DB BW  NOISE BW    RATE
     /// <1><0>               (HZ)     (HZ)        (KHZ)    3-DB BW (HZ)
@@ -150,6 +235,7 @@ impl GYRO_CONFIG {
     /// 0 0          5          10       15.6        1          10
     /// 0 0          6           5        8.0        1           5
     /// 0 0          7        3281     3451.0        8        4000
+    /// ```
     /// Table 16. Configuration
     pub const FCHOICE_B: BitBlock = BitBlock { bit: 1, length: 2 };
 }
@@ -174,14 +260,37 @@ impl ACCEL_CONFIG {
     pub const FS_SEL: BitBlock = BitBlock { bit: 4, length: 2};
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 29: Accel Config 2
 pub struct ACCEL_CONFIG_2;
+
 impl ACCEL_CONFIG_2 {
     /// Base Address
     pub const ADDR: u8 = 0x1d;
+    /// Averaging filter settings for Low Power Accelerometer mode:
+    /// 0 = Average 4 samples, 1 = 8 samples, 2 = 16 samples, 3 = 32 samples
     pub const DEC2_CFG: BitBlock = BitBlock { bit: 5, length: 2 };
-    /// Bits for Accel Bandwidth configuration
+    /// Used to bypass DLPF as shown in table 2 of the register map
     pub const ACCEL_FCHOICE_B: u8 = 3;
+    /// Accelerometer low pass filter setting as shown in table 2 of the register map
     pub const A_DLPF_CFG: BitBlock = BitBlock { bit: 2, length: 3 };
+    /// `ACCEL_FCHOICE_B` and `A_DLPF_CFG` together, as encoded by [`AccelBw`](crate::config::AccelBw)
+    pub const ACCEL_BW: BitBlock = BitBlock { bit: 3, length: 4 };
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 30: Low Power Mode Configuration
+pub struct LP_MODE_CFG;
+
+impl LP_MODE_CFG {
+    /// Base Address
+    pub const ADDR: u8 = 0x1e;
+    /// 1 – Gyro low power mode, averaging by G_AVGCFG
+    pub const GYRO_CYCLE: u8 = 7;
+    /// Averaging filter of the gyro low power mode, 1 to 128 samples
+    pub const G_AVGCFG: BitBlock = BitBlock { bit: 6, length: 3 };
 }
 
 #[allow(non_camel_case_types)]
@@ -243,6 +352,18 @@ impl INT_ENABLE {
     pub const DATA_RDY_EN: u8 = 0;
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 57: FIFO Watermark Interrupt Status
+pub struct FIFO_WM_INT_STATUS;
+
+impl FIFO_WM_INT_STATUS {
+    /// Base Address
+    pub const ADDR: u8 = 0x39;
+    /// FIFO count reached the watermark threshold (FIFO_WM_TH), cleared on read
+    pub const FIFO_WM_INT: u8 = 6;
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug)]
 /// Register 58: Interrupt Status
@@ -268,6 +389,27 @@ impl INT_STATUS {
 
 
 
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+/// Register 105: Accelerometer Intelligence Control
+pub struct ACCEL_INTEL_CTRL;
+
+impl ACCEL_INTEL_CTRL {
+    /// Base Address
+    pub const ADDR: u8 = 0x69;
+    /// Enable the wake on motion detection logic
+    pub const ACCEL_INTEL_EN: u8 = 7;
+    /// 1 – Compare the current sample with the previous sample
+    /// 0 – Compare the current sample with the initial sample, per the MPU6500; the MPU6886
+    /// register map documents 0 as not used
+    pub const ACCEL_INTEL_MODE: u8 = 6;
+    /// 1 – Limit the output data of the accel in wake on motion mode
+    pub const OUTPUT_LIMIT: u8 = 1;
+    /// 1 – Set WoM interrupt on the AND of all enabled accelerometer thresholds
+    /// 0 – Set WoM interrupt on the OR of all enabled accelerometer thresholds
+    pub const WOM_TH_MODE: u8 = 0;
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug)]
 /// Register 107: Power Management 1
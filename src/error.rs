@@ -2,7 +2,7 @@
 /// All possible errors in this crate
 #[derive(Debug)]
 pub enum Mpu6886Error<E> {
-    /// I2C bus error
+    /// Bus error, of the I2C or the SPI bus depending on the interface
     I2c(E),
 
     /// Invalid chip ID was read
@@ -41,6 +41,32 @@ pub enum SensorError {
     InvalidDiscriminant,
     /// no fifo data available
     NofFifoData,
+    /// Requested sample rate can not be reached with SMPLRT_DIV (3.9 Hz to 1 kHz)
+    InvalidSampleRate,
+    /// SMPLRT_DIV has no effect with the current filter configuration
+    /// (FCHOICE_B != 0, DLPF_CFG 0 or 7 or ACCEL_FCHOICE_B = 1)
+    SampleRateDivIneffective,
+    /// Wake on motion threshold above 1020 mg
+    InvalidWomThreshold,
+    /// FIFO watermark above 1023 bytes
+    InvalidFifoWatermark,
+    /// The FIFO packets (FIFO_EN) are not accel, temp and gyro as the read needs, or no
+    /// sensor writes to the FIFO, see `drain_fifo` for all layouts
+    UnsupportedFifoLayout,
+    /// Calibration needs at least 2 samples
+    NotEnoughSamples,
+    /// The sensor moved during calibration, sample deviation above the threshold
+    MotionDuringCalibration,
+    /// Calibration reading does not match the requested orientation
+    UnexpectedOrientation,
+    /// Not all six calibration orientations measured, or the readings are degenerate
+    IncompleteCalibration,
+    /// Calibration blob with wrong magic bytes or unknown format version
+    InvalidCalibrationBlob,
+    /// Calibration blob checksum mismatch, the blob is corrupted
+    CalibrationChecksum,
+    /// Temperature drift fit needs at least 3 points over a wider temperature range
+    InsufficientTempRange,
 }
 
 // impl<E> From<SensorError> for Error<E> {
@@ -0,0 +1,171 @@
+//! Attitude estimation from gyro and accelerometer
+//!
+//! The filters take the scaled readings of the driver, gyro in rad/s and accel in g, either
+//! as arguments, as [`ImuSample`] of `read_all` or as FIFO
+//! [`Sample`]s, together with the time step. For the FIFO the time step is
+//! the sample period, `1.0 / get_sample_rate()` or `FifoClock::period_us`.
+//!
+//! ```ignore
+//! let mut filter = ComplementaryFilter::new(0.5);
+//! let dt = 1.0 / mpu.set_sample_rate_hz(200.0)?;
+//! let mut samples = [Sample::default(); 32];
+//! let drained = mpu.drain_fifo(&mut samples)?.samples;
+//! for sample in &samples[..drained] {
+//!     filter.update_fifo(sample, dt);
+//! }
+//! let [roll, pitch, yaw] = filter.angles().into();
+//! ```
+//!
+//! Angles are in rad, in the sensor frame: roll about x, pitch about y, yaw about z, applied
+//! z-y-x (yaw first). At rest the accelerometer reads +1 g on the axis pointing up. Without
+//! magnetometer the yaw is relative to the start, integrated from the gyro only.
+//!
+//! ### Quaternion filters
+//! [`Madgwick`] and [`Mahony`] estimate the full orientation as `UnitQuaternion`, rotating
+//! vectors of the sensor frame into the earth frame (z up). Both implement [`Ahrs`], which
+//! converts to Euler angles and rotation matrices. Mahony also estimates the gyro bias.
+//!
+//! ### Kalman filter
+//! [`Eskf`] estimates orientation and gyro bias with their covariance, from the datasheet
+//! noise densities at the configured bandwidths, see [`EskfNoise::for_bandwidth`]. It has no
+//! magnetometer input and does not implement `Ahrs`.
+//!
+//! ### External magnetometer
+//! With a magnetometer, `Ahrs::update_mag` also corrects the yaw, the earth x axis then points
+//! to magnetic north. The mag reading must be hard and soft iron calibrated and rotated into
+//! the axes of the mpu6886, its unit does not matter. Mag data arriving slower than the IMU
+//! is passed as `Option` to `update_imu_mag`, steps without it are 6-DoF.
+
+use libm::{atan2f, sqrtf};
+use nalgebra::{Rotation3, UnitQuaternion, Vector3};
+
+use crate::fifo::Sample;
+use crate::raw::ImuSample;
+use crate::PI;
+
+mod complementary;
+mod eskf;
+mod madgwick;
+mod mahony;
+
+pub use complementary::ComplementaryFilter;
+pub use eskf::{Eskf, EskfCovariance, EskfNoise};
+pub use madgwick::Madgwick;
+pub use mahony::Mahony;
+
+/// Attitude and heading reference system, a filter estimating the orientation as quaternion
+pub trait Ahrs {
+    /// One step with gyro in rad/s and accel in g, dt in s. An accel of 0 skips the
+    /// correction, the gyro is integrated only.
+    fn update(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, dt: f32);
+
+    /// One step with an additional magnetometer reading in the sensor frame, see the
+    /// [module documentation](self)
+    fn update_mag(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, mag: Vector3<f32>, dt: f32);
+
+    /// Orientation, rotates vectors of the sensor frame into the earth frame
+    fn quaternion(&self) -> UnitQuaternion<f32>;
+
+    /// One step with a sample of `read_all`
+    fn update_imu(&mut self, sample: &ImuSample, dt: f32) {
+        self.update(sample.gyro, sample.accel, dt);
+    }
+
+    /// One step with a sample of `read_all` and a magnetometer reading, if there is one
+    fn update_imu_mag(&mut self, sample: &ImuSample, mag: Option<Vector3<f32>>, dt: f32) {
+        match mag {
+            Some(mag) => self.update_mag(sample.gyro, sample.accel, mag, dt),
+            None => self.update(sample.gyro, sample.accel, dt),
+        }
+    }
+
+    /// One step with a FIFO sample, with dt the sample period. Without accel in the FIFO the
+    /// gyro is integrated only, without gyro the orientation is corrected towards the accel.
+    fn update_fifo(&mut self, sample: &Sample, dt: f32) {
+        self.update(sample.gyro.unwrap_or_default(), sample.accel.unwrap_or_default(), dt);
+    }
+
+    /// Roll, pitch and yaw in rad, z-y-x like `ComplementaryFilter`
+    fn euler_angles(&self) -> Vector3<f32> {
+        let (roll, pitch, yaw) = self.quaternion().euler_angles();
+        Vector3::new(roll, pitch, yaw)
+    }
+
+    /// Orientation as rotation matrix, columns are the sensor axes in the earth frame
+    fn rotation_matrix(&self) -> Rotation3<f32> {
+        self.quaternion().to_rotation_matrix()
+    }
+}
+
+/// Roll and pitch in rad of the gravity direction measured by the accelerometer
+///
+/// Unlike `get_acc_angles`, roll covers ±180°, so the sensor may be upside down.
+pub fn accel_roll_pitch(acc: &Vector3<f32>) -> (f32, f32) {
+    (atan2f(acc.y, acc.z), atan2f(-acc.x, sqrtf(acc.y * acc.y + acc.z * acc.z)))
+}
+
+/// Orientation from the gravity direction, with yaw from the magnetometer if given, else 0
+pub(crate) fn initial_orientation(accel: &Vector3<f32>, mag: Option<&Vector3<f32>>) -> UnitQuaternion<f32> {
+    let (roll, pitch) = accel_roll_pitch(accel);
+    let tilt = UnitQuaternion::from_euler_angles(roll, pitch, 0.0);
+    let yaw = mag.map_or(0.0, |mag| {
+        let h = tilt * mag;
+        -atan2f(h.y, h.x)
+    });
+    UnitQuaternion::from_euler_angles(roll, pitch, yaw)
+}
+
+/// Horizontal and vertical component of the magnetic field in the earth frame
+pub(crate) fn mag_reference(q: &UnitQuaternion<f32>, mag: &Vector3<f32>) -> (f32, f32) {
+    let h = q * mag;
+    (sqrtf(h.x * h.x + h.y * h.y), h.z)
+}
+
+/// Wraps an angle to -π..π
+pub(crate) fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI) % (2.0 * PI);
+    if wrapped < 0.0 { wrapped + PI } else { wrapped - PI }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::UnitQuaternion;
+
+    /// Accelerometer reading at rest in the given attitude
+    pub(crate) fn gravity(roll: f32, pitch: f32, yaw: f32) -> Vector3<f32> {
+        UnitQuaternion::from_euler_angles(roll, pitch, yaw).inverse() * Vector3::z()
+    }
+
+    /// Magnetometer reading in the given attitude, field pointing north and 60° down
+    pub(crate) fn magnetic(roll: f32, pitch: f32, yaw: f32) -> Vector3<f32> {
+        let dip = 60.0 * crate::PI_180;
+        UnitQuaternion::from_euler_angles(roll, pitch, yaw).inverse() * Vector3::new(libm::cosf(dip), 0.0, -libm::sinf(dip))
+    }
+
+    #[test]
+    fn accel_angles() {
+        for (roll, pitch) in [(0.0, 0.0), (0.3, -0.5), (-2.5, 1.2), (3.0, 0.1)] {
+            let (r, p) = accel_roll_pitch(&gravity(roll, pitch, 0.7));
+            assert!((r - roll).abs() < 1e-5 && (p - pitch).abs() < 1e-5, "{} {}", r, p);
+        }
+    }
+
+    #[test]
+    fn initial() {
+        let q = initial_orientation(&gravity(0.3, -0.2, 1.0), Some(&magnetic(0.3, -0.2, 1.0)));
+        assert!(q.angle_to(&UnitQuaternion::from_euler_angles(0.3, -0.2, 1.0)) < 1e-4);
+        let q = initial_orientation(&gravity(0.3, -0.2, 1.0), None);
+        assert!(q.angle_to(&UnitQuaternion::from_euler_angles(0.3, -0.2, 0.0)) < 1e-4);
+        let (horizontal, vertical) = mag_reference(&UnitQuaternion::from_euler_angles(0.3, -0.2, 1.0), &magnetic(0.3, -0.2, 1.0));
+        assert!((horizontal - 0.5).abs() < 1e-4 && (vertical + 0.866).abs() < 1e-3);
+    }
+
+    #[test]
+    fn wrap() {
+        assert!((wrap_angle(0.5) - 0.5).abs() < 1e-6);
+        assert!((wrap_angle(PI + 0.5) - (0.5 - PI)).abs() < 1e-5);
+        assert!((wrap_angle(-PI - 0.5) - (PI - 0.5)).abs() < 1e-5);
+        assert!((wrap_angle(5.0 * PI) - PI).abs() < 1e-5 || (wrap_angle(5.0 * PI) + PI).abs() < 1e-5);
+    }
+}
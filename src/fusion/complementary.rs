@@ -0,0 +1,261 @@
+//! Complementary filter for roll and pitch, with relative yaw
+
+use libm::{cosf, sinf};
+use nalgebra::Vector3;
+
+use super::{accel_roll_pitch, wrap_angle};
+use crate::PI;
+use crate::fifo::Sample;
+use crate::raw::ImuSample;
+
+/// Roll and pitch from the integrated gyro, pulled towards the accelerometer angles with the
+/// time constant tau, yaw integrated from the gyro only
+///
+/// Accel disturbances faster than tau, e.g. vibration, are suppressed, gyro drift slower than
+/// tau is corrected. Each step blends with alpha = tau / (tau + dt). The first accel reading
+/// initializes roll and pitch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplementaryFilter {
+    time_constant: f32,
+    angles: Vector3<f32>,
+    initialized: bool,
+}
+
+impl ComplementaryFilter {
+    /// Filter with time constant tau in s, angles at 0 until the first accel reading
+    pub fn new(time_constant: f32) -> Self {
+        ComplementaryFilter { time_constant, angles: Vector3::zeros(), initialized: false }
+    }
+
+    /// Time constant in s
+    pub fn time_constant(&self) -> f32 {
+        self.time_constant
+    }
+
+    /// Sets the time constant in s, larger trusts the gyro longer
+    pub fn set_time_constant(&mut self, time_constant: f32) {
+        self.time_constant = time_constant;
+    }
+
+    /// One step with gyro in rad/s and accel in g, dt in s
+    pub fn update(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, dt: f32) {
+        self.propagate(gyro, dt);
+        self.correct(accel, dt);
+    }
+
+    /// One step with a sample of `read_all`
+    pub fn update_imu(&mut self, sample: &ImuSample, dt: f32) {
+        self.update(sample.gyro, sample.accel, dt);
+    }
+
+    /// One step with a FIFO sample, with dt the sample period. Without gyro in the FIFO
+    /// the angles follow the accel with the time constant, without accel the gyro is
+    /// integrated only.
+    pub fn update_fifo(&mut self, sample: &Sample, dt: f32) {
+        if let Some(gyro) = sample.gyro {
+            self.propagate(gyro, dt);
+        }
+        if let Some(accel) = sample.accel {
+            self.correct(accel, dt);
+        }
+    }
+
+    /// Roll, pitch and yaw in rad
+    pub fn angles(&self) -> Vector3<f32> {
+        self.angles
+    }
+
+    /// Roll in rad, -π to π
+    pub fn roll(&self) -> f32 {
+        self.angles.x
+    }
+
+    /// Pitch in rad, -π/2 to π/2
+    pub fn pitch(&self) -> f32 {
+        self.angles.y
+    }
+
+    /// Yaw in rad relative to the start or the last `reset_yaw`, -π to π
+    pub fn yaw(&self) -> f32 {
+        self.angles.z
+    }
+
+    /// Sets the yaw to 0
+    pub fn reset_yaw(&mut self) {
+        self.angles.z = 0.0;
+    }
+
+    /// Back to the state of `new`, the next accel reading initializes roll and pitch again
+    pub fn reset(&mut self) {
+        *self = Self::new(self.time_constant);
+    }
+
+    /// Integrates the body rates into the z-y-x angles
+    fn propagate(&mut self, gyro: Vector3<f32>, dt: f32) {
+        let (roll, pitch) = (self.angles.x, self.angles.y);
+        let (sin_r, cos_r) = (sinf(roll), cosf(roll));
+        // singular at ±90° pitch, the rates are held finite there
+        let cos_p = cosf(pitch).abs().max(1e-3).copysign(cosf(pitch));
+        let rate = Vector3::new(
+            gyro.x + (sin_r * gyro.y + cos_r * gyro.z) * sinf(pitch) / cos_p,
+            cos_r * gyro.y - sin_r * gyro.z,
+            (sin_r * gyro.y + cos_r * gyro.z) / cos_p,
+        );
+        let mut angles = (self.angles + rate * dt).map(wrap_angle);
+        // pitched over ±90°: the same attitude with pitch back in -π/2 to π/2
+        if angles.y.abs() > PI / 2.0 {
+            angles.x = wrap_angle(angles.x + PI);
+            angles.y = PI.copysign(angles.y) - angles.y;
+            angles.z = wrap_angle(angles.z + PI);
+        }
+        self.angles = angles;
+    }
+
+    /// Blends roll and pitch with the accel angles
+    fn correct(&mut self, accel: Vector3<f32>, dt: f32) {
+        if accel.norm_squared() == 0.0 {
+            return;
+        }
+        let (roll, pitch) = accel_roll_pitch(&accel);
+        if !self.initialized {
+            self.angles.x = roll;
+            self.angles.y = pitch;
+            self.initialized = true;
+            return;
+        }
+        let gain = dt / (self.time_constant + dt);
+        self.angles.x = wrap_angle(self.angles.x + gain * wrap_angle(roll - self.angles.x));
+        self.angles.y += gain * (pitch - self.angles.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fusion::tests::gravity;
+    use crate::PI_180;
+
+    const DT: f32 = 0.01;
+
+    #[test]
+    fn initializes_from_accel() {
+        let mut filter = ComplementaryFilter::new(1.0);
+        filter.update(Vector3::zeros(), gravity(0.4, -0.3, 0.0), DT);
+        assert!((filter.angles() - Vector3::new(0.4, -0.3, 0.0)).amax() < 1e-5);
+
+        filter.reset();
+        filter.update(Vector3::zeros(), gravity(-3.0, 0.2, 0.0), DT);
+        assert!((filter.roll() + 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tracks_rotation() {
+        // roll at 90°/s for 1 s, then about body y at 45°/s for 1 s
+        let mut filter = ComplementaryFilter::new(0.5);
+        let rate = 90.0 * PI_180;
+        let mut roll = 0.0;
+        for i in 0..200 {
+            let gyro = if i < 100 { Vector3::new(rate, 0.0, 0.0) } else { Vector3::new(0.0, rate / 2.0, 0.0) };
+            if i < 100 {
+                roll += rate * DT;
+            }
+            filter.update(gyro, gravity(roll, 0.0, 0.0), DT);
+        }
+        // at 90° roll, body y points up: the second rotation is a yaw
+        assert!((filter.roll() - 90.0 * PI_180).abs() < 1e-2, "{:?}", filter.angles());
+        assert!(filter.pitch().abs() < 1e-2, "{:?}", filter.angles());
+        assert!((filter.yaw() - 45.0 * PI_180).abs() < 1e-2, "{:?}", filter.angles());
+    }
+
+    #[test]
+    fn suppresses_vibration() {
+        // level sensor shaken with 0.5 g at 25 Hz along y
+        let mut filter = ComplementaryFilter::new(1.0);
+        let mut max_error: f32 = 0.0;
+        let mut max_accel_error: f32 = 0.0;
+        for i in 0..1000 {
+            let shake = 0.5 * sinf(2.0 * crate::PI * 25.0 * i as f32 * DT + 0.3);
+            let accel = Vector3::new(0.0, shake, 1.0);
+            filter.update(Vector3::zeros(), accel, DT);
+            if i > 300 {
+                max_error = max_error.max(filter.roll().abs());
+                max_accel_error = max_accel_error.max(accel_roll_pitch(&accel).0.abs());
+            }
+        }
+        assert!(max_accel_error > 20.0 * PI_180);
+        assert!(max_error < 2.0 * PI_180, "{}", max_error);
+    }
+
+    #[test]
+    fn corrects_gyro_bias() {
+        // gyro bias 1°/s, tilted sensor at rest: error settles at bias * tau
+        let bias = PI_180;
+        let accel = gravity(0.3, 0.2, 0.0);
+        let mut filter = ComplementaryFilter::new(0.5);
+        for _ in 0..3000 {
+            filter.update(Vector3::new(bias, 0.0, 0.0), accel, DT);
+        }
+        assert!((filter.roll() - 0.3 - bias * 0.5).abs() < 2e-3, "{:?}", filter.angles());
+        assert!((filter.pitch() - 0.2).abs() < 1e-2, "{:?}", filter.angles());
+    }
+
+    #[test]
+    fn pitch_over_90_degrees() {
+        // about body y at 90°/s for 1.5 s, through 90° pitch up to 135°, the long time
+        // constant keeps the accel roll, undefined at 90° pitch, from pulling on the gyro
+        let mut filter = ComplementaryFilter::new(100.0);
+        let rate = 90.0 * PI_180;
+        for i in 1..=150 {
+            filter.update(Vector3::new(0.0, rate, 0.0), gravity(0.0, rate * DT * i as f32, 0.0), DT);
+            assert!(filter.angles().iter().all(|a| a.is_finite()), "{:?}", filter.angles());
+            assert!(filter.pitch().abs() <= PI / 2.0, "{:?}", filter.angles());
+        }
+        // 135° pitch is 45° pitch upside down, facing back
+        assert!((filter.roll().abs() - PI).abs() < 1e-2, "{:?}", filter.angles());
+        assert!((filter.pitch() - 45.0 * PI_180).abs() < 1e-2, "{:?}", filter.angles());
+        assert!((filter.yaw().abs() - PI).abs() < 1e-2, "{:?}", filter.angles());
+    }
+
+    #[test]
+    fn yaw_wraps() {
+        let mut filter = ComplementaryFilter::new(0.5);
+        for _ in 0..150 {
+            filter.update(Vector3::new(0.0, 0.0, 180.0 * PI_180), Vector3::z(), DT);
+        }
+        // 270° is -90°
+        assert!((filter.yaw() + 90.0 * PI_180).abs() < 1e-3, "{}", filter.yaw());
+        filter.reset_yaw();
+        assert_eq!(filter.yaw(), 0.0);
+    }
+
+    #[test]
+    fn fifo_samples() {
+        use crate::sim::Mpu6886Sim;
+        use crate::Mpu6886;
+
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        mpu.enable_fifo(true, true).unwrap();
+        let rate = 45.0 * PI_180;
+        for i in 0..50 {
+            sim.push_sample(gravity(i as f32 * rate * DT, 0.0, 0.0), Vector3::new(rate, 0.0, 0.0), 25.0);
+        }
+
+        let mut filter = ComplementaryFilter::new(0.5);
+        let mut samples = [Sample::default(); 64];
+        let drained = mpu.drain_fifo(&mut samples).unwrap().samples;
+        assert_eq!(drained, 50);
+        for sample in &samples[..drained] {
+            filter.update_fifo(sample, DT);
+        }
+        assert!((filter.roll() - 49.0 * rate * DT).abs() < 1e-2, "{:?}", filter.angles());
+
+        // accel only: follows the accel with the time constant
+        let mut filter = ComplementaryFilter::new(0.1);
+        filter.update_fifo(&Sample { accel: Some(Vector3::z()), gyro: None, temp: None }, DT);
+        for _ in 0..100 {
+            filter.update_fifo(&Sample { accel: Some(gravity(0.5, 0.0, 0.0)), gyro: None, temp: None }, DT);
+        }
+        assert!((filter.roll() - 0.5).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,379 @@
+//! Error-state Kalman filter for attitude and gyro bias
+
+use libm::{sqrt, sqrtf};
+use nalgebra::{Matrix3, SMatrix, UnitQuaternion, Vector3};
+
+use super::initial_orientation;
+use crate::config::{AccelBw, GyroBw};
+use crate::device::{ACCEL_NOISE_DENSITY, GYRO_NOISE_DENSITY};
+use crate::fifo::Sample;
+use crate::raw::ImuSample;
+use crate::{PI, PI_180};
+
+/// Covariance of the error state: attitude error in rad about the earth axes, then gyro bias
+/// error in rad/s in the sensor frame
+pub type EskfCovariance = SMatrix<f32, 6, 6>;
+
+/// The covariance is propagated in f64, yaw variance and converged tilt variance are
+/// about 10 orders apart
+type Covariance = SMatrix<f64, 6, 6>;
+
+/// Initial gyro bias standard deviation in rad/s, the zero rate output tolerance of ±5 dps
+const INITIAL_BIAS_STD: f64 = 5.0 * PI_180 as f64;
+
+/// Initial and maximum standard deviation in rad of the unobservable yaw
+const INITIAL_YAW_STD: f64 = PI as f64;
+
+/// Noise parameters of [`Eskf`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EskfNoise {
+    /// Gyro white noise standard deviation of a sample in rad/s
+    pub gyro: f32,
+    /// Gyro bias random walk in rad/s/√s
+    pub gyro_bias_walk: f32,
+    /// Accelerometer white noise standard deviation of a sample in g
+    pub accel: f32,
+    /// Deviation of |accel| from 1 g in g above which accel updates are rejected
+    pub accel_rejection: f32,
+}
+
+impl EskfNoise {
+    /// Datasheet noise densities (`GYRO_NOISE_DENSITY`, `ACCEL_NOISE_DENSITY`) over the noise
+    /// bandwidth of the configured low pass filters, 0.0005 dps/√s bias walk, 0.1 g rejection
+    pub fn for_bandwidth(gyro_bw: GyroBw, accel_bw: AccelBw) -> Self {
+        EskfNoise {
+            gyro: GYRO_NOISE_DENSITY * PI_180 * sqrtf(gyro_bw.noise_bandwidth()),
+            gyro_bias_walk: 0.0005 * PI_180,
+            accel: ACCEL_NOISE_DENSITY * sqrtf(accel_bw.noise_bandwidth()),
+            accel_rejection: 0.1,
+        }
+    }
+}
+
+impl Default for EskfNoise {
+    /// Noise at the reset bandwidths of gyro and accel
+    fn default() -> Self {
+        Self::for_bandwidth(GyroBw::default(), AccelBw::default())
+    }
+}
+
+/// Error-state Kalman filter estimating orientation and gyro bias jointly
+///
+/// The nominal state is the orientation quaternion, rotating sensor frame vectors into the
+/// earth frame like [`Ahrs`](super::Ahrs), and the gyro bias. `predict` integrates the bias
+/// corrected gyro and propagates the covariance of the 6 dimensional error state, attitude
+/// error about the earth axes and bias error. `update_accel` corrects both with the gravity
+/// direction measured by the accelerometer. Its noise grows with the deviation of |accel|
+/// from 1 g, beyond `EskfNoise::accel_rejection` the reading is taken as linear acceleration
+/// and rejected.
+///
+/// Without magnetometer the yaw and the bias about the vertical axis are unobservable. The
+/// yaw variance grows up to π², with the error about the earth axes it stays apart from the
+/// tilt. The first accepted accel reading initializes the orientation, with the yaw unknown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eskf {
+    noise: EskfNoise,
+    q: UnitQuaternion<f32>,
+    bias: Vector3<f32>,
+    p: Covariance,
+    initialized: bool,
+}
+
+impl Eskf {
+    /// Filter with the given noise, zero bias, identity orientation until the first accel reading
+    pub fn new(noise: EskfNoise) -> Self {
+        let mut p = Covariance::zeros();
+        p.fixed_slice_mut::<3, 3>(0, 0).fill_diagonal(INITIAL_YAW_STD * INITIAL_YAW_STD);
+        p.fixed_slice_mut::<3, 3>(3, 3).fill_diagonal(INITIAL_BIAS_STD * INITIAL_BIAS_STD);
+        Eskf { noise, q: UnitQuaternion::identity(), bias: Vector3::zeros(), p, initialized: false }
+    }
+
+    /// Noise parameters
+    pub fn noise(&self) -> EskfNoise {
+        self.noise
+    }
+
+    /// Sets the noise parameters, e.g. after changing the filter bandwidths
+    pub fn set_noise(&mut self, noise: EskfNoise) {
+        self.noise = noise;
+    }
+
+    /// Propagates orientation and covariance with the gyro in rad/s over dt in s
+    pub fn predict(&mut self, gyro: Vector3<f32>, dt: f32) {
+        self.q *= UnitQuaternion::from_scaled_axis((gyro - self.bias) * dt);
+        self.q.renormalize_fast();
+
+        let dt = dt as f64;
+        let (gyro_noise, bias_walk) = (self.noise.gyro as f64, self.noise.gyro_bias_walk as f64);
+        let mut f = Covariance::identity();
+        f.fixed_slice_mut::<3, 3>(0, 3).copy_from(&(self.q.to_rotation_matrix().matrix().cast::<f64>() * -dt));
+        let mut q = Covariance::zeros();
+        q.fixed_slice_mut::<3, 3>(0, 0).fill_diagonal(gyro_noise * gyro_noise * dt * dt);
+        q.fixed_slice_mut::<3, 3>(3, 3).fill_diagonal(bias_walk * bias_walk * dt);
+        self.p = f * self.p * f.transpose() + q;
+
+        // a yaw beyond ±π is as unknown as π, scaling row and column keeps P positive
+        let yaw_var = self.p[(2, 2)];
+        if yaw_var > INITIAL_YAW_STD * INITIAL_YAW_STD {
+            let scale = INITIAL_YAW_STD / sqrt(yaw_var);
+            self.p.row_mut(2).scale_mut(scale);
+            self.p.column_mut(2).scale_mut(scale);
+        }
+    }
+
+    /// Corrects orientation and bias with the accel in g, returns whether it was accepted
+    pub fn update_accel(&mut self, accel: Vector3<f32>) -> bool {
+        let norm = accel.norm();
+        let deviation = (norm - 1.0).abs();
+        if norm == 0.0 || deviation > self.noise.accel_rejection {
+            return false;
+        }
+        let measured = accel / norm;
+        if !self.initialized {
+            self.initialize(&measured);
+            return true;
+        }
+
+        // predicted gravity direction, h(exp(δθ) ⊗ q) ≈ h + R^T [z]x δθ, independent of the yaw
+        let rotation = self.q.to_rotation_matrix().matrix().cast::<f64>();
+        let predicted = rotation.transpose() * Vector3::z();
+        let mut h = SMatrix::<f64, 3, 6>::zeros();
+        h.fixed_slice_mut::<3, 3>(0, 0).copy_from(&(rotation.transpose() * Vector3::z().cross_matrix()));
+        // linear acceleration as additional noise
+        let accel_noise = self.noise.accel as f64;
+        let deviation = deviation as f64;
+        let r = Matrix3::from_diagonal_element(accel_noise * accel_noise + deviation * deviation);
+
+        let Some(s_inv) = (h * self.p * h.transpose() + r).try_inverse() else {
+            return false;
+        };
+        let k = self.p * h.transpose() * s_inv;
+        let dx = k * (measured.cast::<f64>() - predicted);
+        let dtheta = dx.fixed_rows::<3>(0).into_owned();
+        self.q = UnitQuaternion::from_scaled_axis(dtheta.cast::<f32>()) * self.q;
+        self.bias += dx.fixed_rows::<3>(3).into_owned().cast::<f32>();
+
+        // Joseph form. The reset jacobian I + [δθ/2]x is left out, it would mix the unknown yaw
+        // into the tilt
+        let i_kh = Covariance::identity() - k * h;
+        self.p = i_kh * self.p * i_kh.transpose() + k * r * k.transpose();
+        self.p = (self.p + self.p.transpose()) * 0.5;
+        true
+    }
+
+    /// `predict` and `update_accel`, returns whether the accel was accepted
+    pub fn update(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, dt: f32) -> bool {
+        self.predict(gyro, dt);
+        self.update_accel(accel)
+    }
+
+    /// One step with a sample of `read_all`
+    pub fn update_imu(&mut self, sample: &ImuSample, dt: f32) -> bool {
+        self.update(sample.gyro, sample.accel, dt)
+    }
+
+    /// One step with a FIFO sample, with dt the sample period, the parts in the FIFO are used
+    pub fn update_fifo(&mut self, sample: &Sample, dt: f32) -> bool {
+        if let Some(gyro) = sample.gyro {
+            self.predict(gyro, dt);
+        }
+        sample.accel.is_some_and(|accel| self.update_accel(accel))
+    }
+
+    /// Orientation, rotates vectors of the sensor frame into the earth frame
+    pub fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.q
+    }
+
+    /// Roll, pitch and yaw in rad, z-y-x like `ComplementaryFilter`
+    pub fn euler_angles(&self) -> Vector3<f32> {
+        let (roll, pitch, yaw) = self.q.euler_angles();
+        Vector3::new(roll, pitch, yaw)
+    }
+
+    /// Estimated gyro bias in rad/s, subtracted from the gyro readings
+    pub fn gyro_bias(&self) -> Vector3<f32> {
+        self.bias
+    }
+
+    /// Covariance of attitude error and gyro bias error
+    pub fn covariance(&self) -> EskfCovariance {
+        self.p.cast::<f32>()
+    }
+
+    /// Standard deviation of the attitude error in rad about the earth axes, x and y the tilt,
+    /// z the yaw
+    pub fn attitude_std(&self) -> Vector3<f32> {
+        self.p.fixed_slice::<3, 3>(0, 0).diagonal().map(|var| sqrt(var) as f32)
+    }
+
+    /// Standard deviation of the gyro bias in rad/s
+    pub fn gyro_bias_std(&self) -> Vector3<f32> {
+        self.p.fixed_slice::<3, 3>(3, 3).diagonal().map(|var| sqrt(var) as f32)
+    }
+
+    /// Sets the orientation, the next readings are not used to initialize it
+    pub fn set_quaternion(&mut self, q: UnitQuaternion<f32>) {
+        self.q = q;
+        self.initialized = true;
+    }
+
+    /// Sets the gyro bias, e.g. from `calibrate_gyro` or a previous run
+    pub fn set_gyro_bias(&mut self, bias: Vector3<f32>, std: f32) {
+        self.bias = bias;
+        self.p.fixed_slice_mut::<3, 6>(3, 0).fill(0.0);
+        self.p.fixed_slice_mut::<3, 3>(0, 3).fill(0.0);
+        self.p.fixed_slice_mut::<3, 3>(3, 3).fill_diagonal(std as f64 * std as f64);
+    }
+
+    /// Back to the state of `new`, also clears the bias estimate
+    pub fn reset(&mut self) {
+        *self = Self::new(self.noise);
+    }
+
+    /// Tilt from the gravity direction, with accel noise on roll and pitch and unknown yaw
+    fn initialize(&mut self, measured: &Vector3<f32>) {
+        self.q = initial_orientation(measured, None);
+        let accel_var = self.noise.accel as f64 * self.noise.accel as f64;
+        self.p.fixed_slice_mut::<3, 6>(0, 0).fill(0.0);
+        self.p.fixed_slice_mut::<3, 3>(3, 0).fill(0.0);
+        self.p.fixed_slice_mut::<3, 3>(0, 0)
+            .set_diagonal(&Vector3::new(accel_var, accel_var, INITIAL_YAW_STD * INITIAL_YAW_STD));
+        self.initialized = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fusion::tests::gravity;
+
+    const DT: f32 = 0.01;
+
+    /// Deterministic noise, uniform with standard deviation std
+    struct Noise(u32);
+
+    impl Noise {
+        fn next(&mut self, std: f32) -> f32 {
+            self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((self.0 >> 8) as f32 / (1 << 24) as f32 - 0.5) * sqrtf(12.0) * std
+        }
+
+        fn vector(&mut self, std: f32) -> Vector3<f32> {
+            Vector3::new(self.next(std), self.next(std), self.next(std))
+        }
+    }
+
+    #[test]
+    fn noise_for_bandwidth() {
+        let noise = EskfNoise::for_bandwidth(GyroBw::Hz92, AccelBw::Hz99);
+        assert!((noise.gyro - 0.004 * PI_180 * sqrtf(108.6)).abs() < 1e-7);
+        assert!((noise.accel - 100e-6 * sqrtf(121.3)).abs() < 1e-7);
+        let wide = EskfNoise::default();
+        assert!(wide.gyro > noise.gyro && wide.accel > noise.accel);
+        assert!(EskfNoise::for_bandwidth(GyroBw::Hz5, AccelBw::Hz5).gyro < noise.gyro);
+    }
+
+    #[test]
+    fn initializes_from_accel() {
+        let mut filter = Eskf::new(EskfNoise::default());
+        assert!(filter.update(Vector3::zeros(), gravity(0.3, -0.2, 0.0), DT));
+        assert!((filter.euler_angles() - Vector3::new(0.3, -0.2, 0.0)).amax() < 1e-4);
+
+        // small tilt std, yaw unknown
+        let std = filter.attitude_std();
+        assert!(std.x < 0.01 && std.y < 0.01 && (std.z - PI).abs() < 1e-3, "{:?}", std);
+        assert!((filter.gyro_bias_std() - Vector3::repeat(INITIAL_BIAS_STD as f32)).amax() < 1e-6);
+    }
+
+    #[test]
+    fn estimates_gyro_bias() {
+        let (roll, pitch) = (0.2, 0.1);
+        let bias = Vector3::new(0.02, -0.03, 0.01);
+        let noise = EskfNoise::for_bandwidth(GyroBw::Hz41, AccelBw::Hz45);
+        let mut rng = Noise(1);
+        let mut filter = Eskf::new(noise);
+        for _ in 0..3000 {
+            let gyro = bias + rng.vector(noise.gyro);
+            let accel = gravity(roll, pitch, 0.0) + rng.vector(noise.accel);
+            assert!(filter.update(gyro, accel, DT));
+        }
+
+        // horizontal components observable
+        let vertical = gravity(roll, pitch, 0.0);
+        let error = filter.gyro_bias() - bias;
+        assert!((error - vertical * vertical.dot(&error)).amax() < 1e-3, "{:?}", filter.gyro_bias());
+        let (r, p, _) = filter.quaternion().euler_angles();
+        assert!((r - roll).abs() < 5e-3 && (p - pitch).abs() < 5e-3, "{:?}", filter.euler_angles());
+
+        // covariance: symmetric, shrunk for the horizontal bias, vertical and yaw stay uncertain
+        let cov = filter.covariance();
+        assert!((cov - cov.transpose()).amax() < 1e-9);
+        let bias_std = |axis: Vector3<f32>| sqrtf(axis.dot(&(cov.fixed_slice::<3, 3>(3, 3) * axis)));
+        assert!(bias_std(vertical.cross(&Vector3::x()).normalize()) < 1e-3);
+        assert!(bias_std(vertical) > 0.5 * INITIAL_BIAS_STD as f32, "{:?}", filter.gyro_bias_std());
+        assert!(filter.attitude_std().z > 1.0 && filter.attitude_std().z <= PI);
+    }
+
+    #[test]
+    fn tracks_rotation_within_covariance() {
+        let rate = Vector3::new(0.3, -0.5, 1.0);
+        let bias = Vector3::new(0.01, 0.0, -0.01);
+        let noise = EskfNoise::for_bandwidth(GyroBw::Hz92, AccelBw::Hz99);
+        let mut rng = Noise(7);
+        let mut truth = UnitQuaternion::from_euler_angles(0.1, 0.2, 0.0);
+        let mut filter = Eskf::new(noise);
+        filter.set_quaternion(truth);
+        for _ in 0..1000 {
+            truth *= UnitQuaternion::from_scaled_axis(rate * DT);
+            let accel = truth.inverse_transform_vector(&Vector3::z()) + rng.vector(noise.accel);
+            filter.update(rate + bias + rng.vector(noise.gyro), accel, DT);
+        }
+        // tilt error about the earth x and y axes within 3 sigma of the filter
+        let error = (truth * filter.quaternion().inverse()).scaled_axis();
+        let std = filter.attitude_std();
+        assert!(error.x.abs() < 3.0 * std.x && error.y.abs() < 3.0 * std.y, "{:?} {:?}", error, std);
+        assert!(error.xy().norm() < 1e-2);
+    }
+
+    #[test]
+    fn rejects_linear_acceleration() {
+        let mut filter = Eskf::new(EskfNoise::default());
+        filter.update(Vector3::zeros(), Vector3::z(), DT);
+        filter.set_quaternion(UnitQuaternion::from_euler_angles(0.05, 0.0, 0.0));
+
+        // 0.6 g sideways: rejected, orientation and covariance only predicted
+        let before = filter;
+        assert!(!filter.update(Vector3::zeros(), Vector3::new(0.0, 0.6, 1.0), DT));
+        let mut predicted = before;
+        predicted.predict(Vector3::zeros(), DT);
+        assert_eq!(filter, predicted);
+
+        // small deviations accepted, with less weight
+        let mut exact = filter;
+        let mut deviating = filter;
+        assert!(exact.update_accel(Vector3::z()));
+        assert!(deviating.update_accel(Vector3::z() * 1.05));
+        let roll = |f: &Eskf| f.euler_angles().x;
+        assert!(roll(&exact) < roll(&deviating) && roll(&deviating) < 0.05);
+        assert!(exact.attitude_std().x < deviating.attitude_std().x);
+        assert!(!filter.update_accel(Vector3::zeros()));
+    }
+
+    #[test]
+    fn fifo_samples() {
+        let mut filter = Eskf::new(EskfNoise::default());
+        let accel = Sample { accel: Some(gravity(0.2, 0.0, 0.0)), gyro: None, temp: None };
+        assert!(filter.update_fifo(&accel, DT));
+        let gyro = Sample { accel: None, gyro: Some(Vector3::new(0.5, 0.0, 0.0)), temp: None };
+        assert!(!filter.update_fifo(&gyro, DT));
+        assert!((filter.euler_angles().x - 0.205).abs() < 1e-4);
+
+        filter.set_gyro_bias(Vector3::new(0.5, 0.0, 0.0), 0.01);
+        filter.update_fifo(&gyro, DT);
+        assert!((filter.euler_angles().x - 0.205).abs() < 1e-4);
+        assert!((filter.gyro_bias_std().x - 0.01).abs() < 1e-6);
+        filter.reset();
+        assert_eq!(filter.gyro_bias(), Vector3::zeros());
+    }
+}
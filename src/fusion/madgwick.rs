@@ -0,0 +1,189 @@
+//! Madgwick gradient descent orientation filter
+
+use nalgebra::{Quaternion, SMatrix, UnitQuaternion, Vector3, Vector4};
+
+use super::{initial_orientation, mag_reference, Ahrs};
+
+/// Madgwick filter: integrates the gyro and steps along the gradient that aligns the predicted
+/// gravity (and magnetic field) direction with the measured one, at beta rad/s
+///
+/// Larger beta converges faster but follows accel disturbances more, Madgwick suggests
+/// √(3/4) times the gyro measurement error. The first reading with accel initializes roll
+/// and pitch, and the yaw from the mag if given.
+///
+/// Sebastian Madgwick, "An efficient orientation filter for inertial and inertial/magnetic
+/// sensor arrays", 2010
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Madgwick {
+    beta: f32,
+    q: UnitQuaternion<f32>,
+    initialized: bool,
+}
+
+impl Madgwick {
+    /// Filter with gain beta in rad/s, identity orientation until the first accel reading
+    pub fn new(beta: f32) -> Self {
+        Madgwick { beta, q: UnitQuaternion::identity(), initialized: false }
+    }
+
+    /// Gain in rad/s
+    pub fn beta(&self) -> f32 {
+        self.beta
+    }
+
+    /// Sets the gain in rad/s
+    pub fn set_beta(&mut self, beta: f32) {
+        self.beta = beta;
+    }
+
+    /// Sets the orientation, the next readings are not used to initialize it
+    pub fn set_quaternion(&mut self, q: UnitQuaternion<f32>) {
+        self.q = q;
+        self.initialized = true;
+    }
+
+    /// Back to the state of `new`
+    pub fn reset(&mut self) {
+        *self = Self::new(self.beta);
+    }
+
+    fn step(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, mag: Option<Vector3<f32>>, dt: f32) {
+        let Some(a) = accel.try_normalize(0.0) else {
+            self.integrate(gyro, Vector4::zeros(), dt);
+            return;
+        };
+        let m = mag.and_then(|mag| mag.try_normalize(0.0));
+        if !self.initialized {
+            self.q = initial_orientation(&a, m.as_ref());
+            self.initialized = true;
+            return;
+        }
+
+        let q = self.q.quaternion();
+        let (q0, q1, q2, q3) = (q.w, q.i, q.j, q.k);
+        // predicted minus measured gravity in the sensor frame, and its jacobian
+        let f_g = Vector3::new(
+            2.0 * (q1 * q3 - q0 * q2) - a.x,
+            2.0 * (q0 * q1 + q2 * q3) - a.y,
+            2.0 * (0.5 - q1 * q1 - q2 * q2) - a.z,
+        );
+        let j_g = SMatrix::<f32, 3, 4>::new(
+            -2.0 * q2, 2.0 * q3, -2.0 * q0, 2.0 * q1,
+            2.0 * q1, 2.0 * q0, 2.0 * q3, 2.0 * q2,
+            0.0, -4.0 * q1, -4.0 * q2, 0.0,
+        );
+        let mut gradient = j_g.transpose() * f_g;
+
+        if let Some(m) = m {
+            let (bx, bz) = mag_reference(&self.q, &m);
+            let f_b = Vector3::new(
+                2.0 * bx * (0.5 - q2 * q2 - q3 * q3) + 2.0 * bz * (q1 * q3 - q0 * q2) - m.x,
+                2.0 * bx * (q1 * q2 - q0 * q3) + 2.0 * bz * (q0 * q1 + q2 * q3) - m.y,
+                2.0 * bx * (q0 * q2 + q1 * q3) + 2.0 * bz * (0.5 - q1 * q1 - q2 * q2) - m.z,
+            );
+            let j_b = SMatrix::<f32, 3, 4>::new(
+                -2.0 * bz * q2, 2.0 * bz * q3, -4.0 * bx * q2 - 2.0 * bz * q0, -4.0 * bx * q3 + 2.0 * bz * q1,
+                -2.0 * bx * q3 + 2.0 * bz * q1, 2.0 * bx * q2 + 2.0 * bz * q0, 2.0 * bx * q1 + 2.0 * bz * q3, -2.0 * bx * q0 + 2.0 * bz * q2,
+                2.0 * bx * q2, 2.0 * bx * q3 - 4.0 * bz * q1, 2.0 * bx * q0 - 4.0 * bz * q2, 2.0 * bx * q1,
+            );
+            gradient += j_b.transpose() * f_b;
+        }
+
+        self.integrate(gyro, gradient.try_normalize(0.0).unwrap_or_default() * self.beta, dt);
+    }
+
+    /// q += (q ⊗ gyro / 2 - correction) dt, correction as w, i, j, k
+    fn integrate(&mut self, gyro: Vector3<f32>, correction: Vector4<f32>, dt: f32) {
+        let q = self.q.quaternion();
+        let q_dot = q * Quaternion::from_imag(gyro) * 0.5
+            - Quaternion::new(correction[0], correction[1], correction[2], correction[3]);
+        self.q = UnitQuaternion::from_quaternion(q + q_dot * dt);
+    }
+}
+
+impl Ahrs for Madgwick {
+    fn update(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, dt: f32) {
+        self.step(gyro, accel, None, dt);
+    }
+
+    fn update_mag(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, mag: Vector3<f32>, dt: f32) {
+        self.step(gyro, accel, Some(mag), dt);
+    }
+
+    fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fifo::Sample;
+    use crate::fusion::tests::{gravity, magnetic};
+    use crate::PI_180;
+
+    const DT: f32 = 0.01;
+
+    #[test]
+    fn converges_to_tilt() {
+        let mut filter = Madgwick::new(0.5);
+        filter.update(Vector3::zeros(), gravity(0.4, -0.3, 0.0), DT);
+        assert!(filter.quaternion().angle_to(&UnitQuaternion::from_euler_angles(0.4, -0.3, 0.0)) < 1e-4);
+
+        // 30° off
+        filter.set_quaternion(UnitQuaternion::from_euler_angles(-0.1, 0.2, 0.5));
+        for _ in 0..500 {
+            filter.update(Vector3::zeros(), gravity(0.4, -0.3, 0.5), DT);
+        }
+        // only the gravity direction is observable, the normalized steps chatter by beta * dt
+        let predicted = filter.quaternion().inverse_transform_vector(&Vector3::z());
+        assert!((predicted - gravity(0.4, -0.3, 0.5)).amax() < 0.5 * DT, "{:?}", filter.euler_angles());
+    }
+
+    #[test]
+    fn tracks_rotation() {
+        // constant rate about a tilted axis, accel consistent with the true attitude
+        let rate = Vector3::new(0.5, -0.3, 1.0);
+        let mut truth = UnitQuaternion::from_euler_angles(0.2, 0.1, 0.0);
+        let mut filter = Madgwick::new(0.1);
+        filter.set_quaternion(truth);
+        for _ in 0..300 {
+            truth *= UnitQuaternion::from_scaled_axis(rate * DT);
+            filter.update(rate, truth.inverse() * Vector3::z(), DT);
+        }
+        assert!(filter.quaternion().angle_to(&truth) < 1e-2, "{}", filter.quaternion().angle_to(&truth));
+    }
+
+    #[test]
+    fn heading_from_mag() {
+        let (roll, pitch, yaw) = (0.3, -0.2, 1.2);
+        let mut filter = Madgwick::new(0.5);
+        filter.update_mag(Vector3::zeros(), gravity(roll, pitch, yaw), magnetic(roll, pitch, yaw), DT);
+        assert!((filter.euler_angles() - Vector3::new(roll, pitch, yaw)).amax() < 1e-3);
+
+        // heading off by 40°, pulled back by the mag
+        filter.set_quaternion(UnitQuaternion::from_euler_angles(roll, pitch, yaw + 40.0 * PI_180));
+        for _ in 0..500 {
+            filter.update_mag(Vector3::zeros(), gravity(roll, pitch, yaw), magnetic(roll, pitch, yaw), DT);
+        }
+        assert!((filter.euler_angles() - Vector3::new(roll, pitch, yaw)).amax() < 1e-2, "{:?}", filter.euler_angles());
+    }
+
+    #[test]
+    fn conversions() {
+        let mut filter = Madgwick::new(0.1);
+        filter.set_quaternion(UnitQuaternion::from_euler_angles(0.0, 0.0, 90.0 * PI_180));
+        assert!((filter.euler_angles() - Vector3::new(0.0, 0.0, 90.0 * PI_180)).amax() < 1e-5);
+        // sensor x points to earth y
+        let rotation = filter.rotation_matrix();
+        assert!((rotation * Vector3::x() - Vector3::y()).amax() < 1e-5);
+        assert!((rotation.matrix().column(0) - Vector3::y()).amax() < 1e-5);
+
+        // gyro only FIFO sample integrates, 90°/s about z for 1 s
+        for _ in 0..100 {
+            filter.update_fifo(&Sample { accel: None, gyro: Some(Vector3::new(0.0, 0.0, 90.0 * PI_180)), temp: None }, DT);
+        }
+        assert!((filter.euler_angles().z - 180.0 * PI_180).abs() < 1e-3
+            || (filter.euler_angles().z + 180.0 * PI_180).abs() < 1e-3, "{:?}", filter.euler_angles());
+    }
+}
@@ -0,0 +1,171 @@
+//! Mahony complementary filter on the rotation group, with gyro bias estimation
+
+use nalgebra::{UnitQuaternion, Vector3};
+
+use super::{initial_orientation, mag_reference, Ahrs};
+use crate::PI_180;
+
+/// Limit of the bias estimate in rad/s, the gyro zero rate output is within ±20 dps
+const MAX_BIAS: f32 = 20.0 * PI_180;
+
+/// Mahony filter: the cross product of measured and predicted gravity (and magnetic field)
+/// direction feeds back into the gyro, proportional with kp and integrated with ki
+///
+/// The integral converges to the negative gyro bias, see `gyro_bias`. Without mag the bias
+/// about the vertical axis is unobservable. Typical gains are kp = 1 and ki = 0.01 to 0.1 in
+/// 1/s; ki = 0 disables the bias estimation. The first reading with accel initializes the
+/// orientation like `Madgwick`.
+///
+/// Robert Mahony et al., "Nonlinear complementary filters on the special orthogonal group", 2008
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mahony {
+    kp: f32,
+    ki: f32,
+    q: UnitQuaternion<f32>,
+    integral: Vector3<f32>,
+    initialized: bool,
+}
+
+impl Mahony {
+    /// Filter with proportional gain kp and integral gain ki, identity orientation until the
+    /// first accel reading
+    pub fn new(kp: f32, ki: f32) -> Self {
+        Mahony { kp, ki, q: UnitQuaternion::identity(), integral: Vector3::zeros(), initialized: false }
+    }
+
+    /// Proportional and integral gain
+    pub fn gains(&self) -> (f32, f32) {
+        (self.kp, self.ki)
+    }
+
+    /// Sets the proportional and integral gain, ki = 0 also clears the bias estimate
+    pub fn set_gains(&mut self, kp: f32, ki: f32) {
+        self.kp = kp;
+        self.ki = ki;
+        if ki == 0.0 {
+            self.integral = Vector3::zeros();
+        }
+    }
+
+    /// Estimated gyro bias in rad/s, subtracted from the gyro readings
+    pub fn gyro_bias(&self) -> Vector3<f32> {
+        -self.integral
+    }
+
+    /// Sets the orientation, the next readings are not used to initialize it
+    pub fn set_quaternion(&mut self, q: UnitQuaternion<f32>) {
+        self.q = q;
+        self.initialized = true;
+    }
+
+    /// Back to the state of `new`, also clears the bias estimate
+    pub fn reset(&mut self) {
+        *self = Self::new(self.kp, self.ki);
+    }
+
+    fn step(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, mag: Option<Vector3<f32>>, dt: f32) {
+        let mut gyro = gyro + self.integral;
+        if let Some(a) = accel.try_normalize(0.0) {
+            let m = mag.and_then(|mag| mag.try_normalize(0.0));
+            if !self.initialized {
+                self.q = initial_orientation(&a, m.as_ref());
+                self.initialized = true;
+                return;
+            }
+
+            // rotation from the predicted to the measured directions
+            let mut error = a.cross(&self.q.inverse_transform_vector(&Vector3::z()));
+            if let Some(m) = m {
+                let (bx, bz) = mag_reference(&self.q, &m);
+                error += m.cross(&self.q.inverse_transform_vector(&Vector3::new(bx, 0.0, bz)));
+            }
+            if self.ki > 0.0 {
+                self.integral += error * (self.ki * dt);
+                // no windup beyond the bias range of the sensor
+                self.integral = self.integral.map(|b| b.clamp(-MAX_BIAS, MAX_BIAS));
+            }
+            gyro += error * self.kp;
+        }
+        self.q *= UnitQuaternion::from_scaled_axis(gyro * dt);
+        // rounding errors accumulate in the products
+        self.q.renormalize_fast();
+    }
+}
+
+impl Ahrs for Mahony {
+    fn update(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, dt: f32) {
+        self.step(gyro, accel, None, dt);
+    }
+
+    fn update_mag(&mut self, gyro: Vector3<f32>, accel: Vector3<f32>, mag: Vector3<f32>, dt: f32) {
+        self.step(gyro, accel, Some(mag), dt);
+    }
+
+    fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fusion::tests::{gravity, magnetic};
+    use crate::raw::ImuSample;
+
+    const DT: f32 = 0.01;
+
+    #[test]
+    fn converges_to_tilt() {
+        let mut filter = Mahony::new(2.0, 0.0);
+        filter.set_quaternion(UnitQuaternion::from_euler_angles(-0.1, 0.2, 0.5));
+        for _ in 0..500 {
+            filter.update(Vector3::zeros(), gravity(0.4, -0.3, 0.5), DT);
+        }
+        // only the gravity direction is observable
+        let predicted = filter.quaternion().inverse_transform_vector(&Vector3::z());
+        assert!((predicted - gravity(0.4, -0.3, 0.5)).amax() < 1e-3, "{:?}", filter.euler_angles());
+    }
+
+    #[test]
+    fn estimates_gyro_bias() {
+        let bias = Vector3::new(0.02, -0.03, 0.01);
+        let (roll, pitch, yaw) = (0.2, 0.1, -0.5);
+        let sample = ImuSample { accel: gravity(roll, pitch, yaw), gyro: bias, temp: 25.0 };
+
+        // tilt axes without mag
+        let mut filter = Mahony::new(1.0, 0.1);
+        for _ in 0..6000 {
+            filter.update_imu(&sample, DT);
+        }
+        let vertical = gravity(roll, pitch, yaw);
+        let horizontal_error = (filter.gyro_bias() - bias) - vertical * vertical.dot(&(filter.gyro_bias() - bias));
+        assert!(horizontal_error.amax() < 1e-3, "{:?}", filter.gyro_bias());
+        let (r, p, _) = filter.quaternion().euler_angles();
+        assert!((r - roll).abs() < 1e-2 && (p - pitch).abs() < 1e-2);
+
+        // all axes with mag
+        let mut filter = Mahony::new(1.0, 0.1);
+        for _ in 0..6000 {
+            filter.update_imu_mag(&sample, Some(magnetic(roll, pitch, yaw)), DT);
+        }
+        assert!((filter.gyro_bias() - bias).amax() < 1e-3, "{:?}", filter.gyro_bias());
+        assert!((filter.euler_angles() - Vector3::new(roll, pitch, yaw)).amax() < 1e-2, "{:?}", filter.euler_angles());
+
+        filter.set_gains(1.0, 0.0);
+        assert_eq!(filter.gyro_bias(), Vector3::zeros());
+    }
+
+    #[test]
+    fn tracks_rotation() {
+        let rate = Vector3::new(-0.4, 0.6, 90.0 * PI_180);
+        let mut truth = UnitQuaternion::from_euler_angles(0.1, -0.2, 0.3);
+        let mut filter = Mahony::new(1.0, 0.05);
+        filter.update_mag(rate, truth.inverse() * Vector3::z(), magnetic(0.1, -0.2, 0.3), DT);
+        for _ in 0..300 {
+            truth *= UnitQuaternion::from_scaled_axis(rate * DT);
+            let (r, p, y) = truth.euler_angles();
+            filter.update_mag(rate, gravity(r, p, y), magnetic(r, p, y), DT);
+        }
+        assert!(filter.quaternion().angle_to(&truth) < 1e-2, "{}", filter.quaternion().angle_to(&truth));
+    }
+}
@@ -0,0 +1,406 @@
+//! Bus interfaces the driver can talk to the mpu6886 through
+//!
+//! Every register access of [`Mpu6886`](crate::Mpu6886) goes through the
+//! [`Interface`] trait, so all driver methods work unchanged over I2C or SPI.
+//!
+//! Both `embedded-hal` 0.2 (feature `eh0`, default) and 1.0 (feature `eh1`) buses are supported.
+//! The hal version is tracked by the [`Eh0`] and [`Eh1`] markers, this way the driver knows
+//! which delay trait to expect and both features can be enabled at the same time.
+
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+use core::marker::PhantomData;
+
+#[cfg(feature = "eh0")]
+use embedded_hal::{
+    blocking::i2c,
+    blocking::spi,
+    digital::v2::OutputPin,
+};
+
+/// SPI read flag, MSB of the register address
+///
+/// The first byte of a SPI transaction is the register address,
+/// bit 7 set means read, cleared means write.
+pub const SPI_READ: u8 = 0x80;
+
+/// Marker for buses and delays implementing `embedded-hal` 0.2 traits
+#[cfg(feature = "eh0")]
+#[derive(Copy, Clone, Debug)]
+pub struct Eh0;
+
+/// Marker for buses and delays implementing `embedded-hal` 1.0 traits
+#[cfg(feature = "eh1")]
+#[derive(Copy, Clone, Debug)]
+pub struct Eh1;
+
+/// Register level access to the mpu6886
+pub trait Interface {
+    /// Error of the underlying bus
+    type Error;
+
+    /// hal version of the bus ([`Eh0`] or [`Eh1`]), delays passed to the driver must match it
+    type Hal;
+
+    /// Writes byte to register
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), Self::Error>;
+
+    /// Reads series of bytes into buf, starting at reg (auto increment)
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Blocking delay of the hal version H
+///
+/// Implemented for every `embedded-hal` 0.2 `DelayMs<u8>` and every `embedded-hal` 1.0 `DelayNs`.
+pub trait Delay<H> {
+    /// Pauses execution for ms milliseconds
+    fn delay_ms(&mut self, ms: u8);
+}
+
+#[cfg(feature = "eh0")]
+impl<D> Delay<Eh0> for D
+where
+    D: embedded_hal::blocking::delay::DelayMs<u8>,
+{
+    fn delay_ms(&mut self, ms: u8) {
+        embedded_hal::blocking::delay::DelayMs::delay_ms(self, ms);
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<D> Delay<Eh1> for D
+where
+    D: embedded_hal_1::delay::DelayNs,
+{
+    fn delay_ms(&mut self, ms: u8) {
+        embedded_hal_1::delay::DelayNs::delay_ms(self, ms as u32);
+    }
+}
+
+/// I2C bus with slave address of the mpu6886
+///
+/// H is the hal version of the bus, it is inferred from the traits the bus implements.
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+pub struct I2cInterface<I, H> {
+    i2c: I,
+    slave_addr: u8,
+    _hal: PhantomData<H>,
+}
+
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+impl<I, H> I2cInterface<I, H> {
+    /// Wraps i2c bus, talking to the chip at slave_addr
+    pub fn new(i2c: I, slave_addr: u8) -> Self {
+        I2cInterface { i2c, slave_addr, _hal: PhantomData }
+    }
+
+    /// Returns the wrapped i2c bus
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<I, E> Interface for I2cInterface<I, Eh0>
+where
+    I: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    type Error = E;
+    type Hal = Eh0;
+
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), E> {
+        self.i2c.write(self.slave_addr, &[reg, byte])
+    }
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), E> {
+        self.i2c.write_read(self.slave_addr, &[reg], buf)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<I> Interface for I2cInterface<I, Eh1>
+where
+    I: embedded_hal_1::i2c::I2c,
+{
+    type Error = I::Error;
+    type Hal = Eh1;
+
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), I::Error> {
+        self.i2c.write(self.slave_addr, &[reg, byte])
+    }
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), I::Error> {
+        self.i2c.write_read(self.slave_addr, &[reg], buf)
+    }
+}
+
+/// Errors of the SPI interface
+#[cfg(feature = "eh0")]
+#[derive(Debug)]
+pub enum SpiError<SE, PE> {
+    /// SPI bus error
+    Spi(SE),
+    /// Chip select pin error
+    Pin(PE),
+}
+
+/// `embedded-hal` 0.2 SPI bus with (active low) chip select pin of the mpu6886
+///
+/// The mpu6886 supports SPI mode 0 and 3 with up to 1 MHz for register writes
+/// and up to 10 MHz for reading sensor and FIFO data, configuring the bus is up to the caller.
+#[cfg(feature = "eh0")]
+pub struct SpiInterface<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+}
+
+#[cfg(feature = "eh0")]
+impl<SPI, CS> SpiInterface<SPI, CS> {
+    /// Wraps spi bus and chip select pin
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        SpiInterface { spi, cs }
+    }
+
+    /// Returns the wrapped spi bus and chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    /// Runs f with chip select asserted, chip select is released even if f fails
+    fn transaction<F, SE, PE>(&mut self, f: F) -> Result<(), SpiError<SE, PE>>
+    where
+        CS: OutputPin<Error = PE>,
+        F: FnOnce(&mut SPI) -> Result<(), SE>,
+    {
+        self.cs.set_low().map_err(SpiError::Pin)?;
+        let result = f(&mut self.spi);
+        let cs = self.cs.set_high();
+        // the bus error came first, it is kept if releasing chip select fails as well
+        result.map_err(SpiError::Spi)?;
+        cs.map_err(SpiError::Pin)
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<SPI, CS, SE, PE> Interface for SpiInterface<SPI, CS>
+where
+    SPI: spi::Transfer<u8, Error = SE> + spi::Write<u8, Error = SE>,
+    CS: OutputPin<Error = PE>,
+{
+    type Error = SpiError<SE, PE>;
+    type Hal = Eh0;
+
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), Self::Error> {
+        self.transaction(|spi| spi.write(&[reg & !SPI_READ, byte]))
+    }
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.transaction(|spi| {
+            spi.write(&[reg | SPI_READ])?;
+            buf.fill(0);
+            spi.transfer(buf)?;
+            Ok(())
+        })
+    }
+}
+
+/// `embedded-hal` 1.0 SPI device of the mpu6886, chip select is handled by the device
+///
+/// Same bus limits as for [`SpiInterface`] apply.
+#[cfg(feature = "eh1")]
+pub struct SpiDeviceInterface<SPI> {
+    spi: SPI,
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> SpiDeviceInterface<SPI> {
+    /// Wraps spi device
+    pub fn new(spi: SPI) -> Self {
+        SpiDeviceInterface { spi }
+    }
+
+    /// Returns the wrapped spi device
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> Interface for SpiDeviceInterface<SPI>
+where
+    SPI: embedded_hal_1::spi::SpiDevice,
+{
+    type Error = SPI::Error;
+    type Hal = Eh1;
+
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), SPI::Error> {
+        self.spi.write(&[reg & !SPI_READ, byte])
+    }
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), SPI::Error> {
+        use embedded_hal_1::spi::Operation;
+
+        self.spi.transaction(&mut [Operation::Write(&[reg | SPI_READ]), Operation::Read(buf)])
+    }
+}
+
+#[cfg(all(test, feature = "eh0"))]
+mod eh0_tests {
+    use super::*;
+    extern crate std;
+    use std::vec;
+
+    use embedded_hal_mock::eh0::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+    use embedded_hal_mock::eh0::MockError;
+    use embedded_hal_mock::eh0::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use embedded_hal_mock::eh0::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    use crate::device::*;
+
+    fn cs_cycle(n: usize) -> std::vec::Vec<PinTransaction> {
+        (0..n)
+            .flat_map(|_| [PinTransaction::set(State::Low), PinTransaction::set(State::High)])
+            .collect()
+    }
+
+    #[test]
+    fn i2c_register_access() {
+        let mut i2c = I2cMock::new(&[
+            I2cTransaction::write(DEFAULT_SLAVE_ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+            I2cTransaction::write_read(DEFAULT_SLAVE_ADDR, vec![ACC_REGX_H], vec![1, 2, 3]),
+        ]);
+        let mut iface = I2cInterface::<_, Eh0>::new(i2c.clone(), DEFAULT_SLAVE_ADDR);
+
+        iface.write_register(PWR_MGMT_1::ADDR, 0x01).unwrap();
+        let mut buf = [0; 3];
+        iface.read_registers(ACC_REGX_H, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3]);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn spi_read_sets_read_bit() {
+        let mut spi = SpiMock::new(&[
+            SpiTransaction::write(vec![WHOAMI | SPI_READ]),
+            SpiTransaction::transfer(vec![0], vec![0x19]),
+            SpiTransaction::write(vec![ACC_REGX_H | SPI_READ]),
+            SpiTransaction::transfer(vec![0; 6], vec![1, 2, 3, 4, 5, 6]),
+        ]);
+        let mut cs = PinMock::new(&cs_cycle(2));
+        let mut iface = SpiInterface::new(spi.clone(), cs.clone());
+
+        let mut byte = [0xff; 1];
+        iface.read_registers(WHOAMI, &mut byte).unwrap();
+        assert_eq!(byte, [0x19]);
+        let mut buf = [0xff; 6];
+        iface.read_registers(ACC_REGX_H, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+
+        spi.done();
+        cs.done();
+    }
+
+    #[test]
+    fn spi_write_clears_read_bit() {
+        let mut spi = SpiMock::new(&[
+            SpiTransaction::write(vec![GYRO_CONFIG::ADDR, 0x18]),
+        ]);
+        let mut cs = PinMock::new(&cs_cycle(1));
+        let mut iface = SpiInterface::new(spi.clone(), cs.clone());
+
+        iface.write_register(GYRO_CONFIG::ADDR, 0x18).unwrap();
+
+        spi.done();
+        cs.done();
+    }
+
+    /// SPI bus failing every transfer
+    struct FailingSpi;
+
+    impl spi::Write<u8> for FailingSpi {
+        type Error = ();
+
+        fn write(&mut self, _: &[u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    impl spi::Transfer<u8> for FailingSpi {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, _: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn spi_error_kept_over_chip_select_error() {
+        let error = MockError::Io(std::io::ErrorKind::Other);
+        let mut cs = PinMock::new(&[
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High).with_error(error.clone()),
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High).with_error(error.clone()),
+        ]);
+        let mut iface = SpiInterface::new(FailingSpi, cs.clone());
+
+        // chip select is released although the transfer failed, the transfer error is reported
+        assert!(matches!(iface.write_register(GYRO_CONFIG::ADDR, 0x18), Err(SpiError::Spi(()))));
+
+        let mut spi = SpiMock::new(&[SpiTransaction::write(vec![GYRO_CONFIG::ADDR, 0x18])]);
+        let mut iface = SpiInterface::new(spi.clone(), cs.clone());
+        assert!(matches!(iface.write_register(GYRO_CONFIG::ADDR, 0x18), Err(SpiError::Pin(e)) if e == error));
+
+        spi.done();
+        cs.done();
+    }
+}
+
+#[cfg(all(test, feature = "eh1"))]
+mod eh1_tests {
+    use super::*;
+    extern crate std;
+    use std::vec;
+
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+    use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+    use crate::device::*;
+
+    #[test]
+    fn i2c_register_access() {
+        let mut i2c = I2cMock::new(&[
+            I2cTransaction::write(DEFAULT_SLAVE_ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+            I2cTransaction::write_read(DEFAULT_SLAVE_ADDR, vec![ACC_REGX_H], vec![1, 2, 3]),
+        ]);
+        let mut iface = I2cInterface::<_, Eh1>::new(i2c.clone(), DEFAULT_SLAVE_ADDR);
+
+        iface.write_register(PWR_MGMT_1::ADDR, 0x01).unwrap();
+        let mut buf = [0; 3];
+        iface.read_registers(ACC_REGX_H, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3]);
+
+        i2c.done();
+    }
+
+    #[test]
+    fn spi_device_register_access() {
+        let mut spi = SpiMock::new(&[
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![GYRO_CONFIG::ADDR, 0x18]),
+            SpiTransaction::transaction_end(),
+            SpiTransaction::transaction_start(),
+            SpiTransaction::write_vec(vec![ACC_REGX_H | SPI_READ]),
+            SpiTransaction::read_vec(vec![1, 2, 3, 4, 5, 6]),
+            SpiTransaction::transaction_end(),
+        ]);
+        let mut iface = SpiDeviceInterface::new(spi.clone());
+
+        iface.write_register(GYRO_CONFIG::ADDR, 0x18).unwrap();
+        let mut buf = [0; 6];
+        iface.read_registers(ACC_REGX_H, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+
+        spi.done();
+    }
+}
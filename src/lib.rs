@@ -1,13 +1,13 @@
 //! # mpu6886 sensor driver.
 //!
-//! `embedded_hal` based driver with i2c access to mpu6886
+//! `embedded_hal` based driver with i2c or spi access to mpu6886
 //!
 //! ### Misc
 //! * [Register sheet](https://www.invensense.com/wp-content/uploads/2015/02/MPU-6000-Register-Map1.pdf),
 //! * [Data sheet](https://www.invensense.com/wp-content/uploads/2015/02/MPU-6500-Datasheet2.pdf)
 //! 
 //! To use this driver you must provide a concrete `embedded_hal` implementation.
-//! This example uses `linux_embedded_hal`.
+//! This example uses `linux_embedded_hal` and the scaled readings of the feature `float`.
 //!
 //! **More Examples** can be found [here](https://github.com/juliangaal/mpu6886/tree/master/examples).
 //! ```no_run
@@ -16,12 +16,14 @@
 //! use i2cdev::linux::LinuxI2CError;
 //! 
 //!
-//! fn main() -> Result<(), mpu6886Error<LinuxI2CError>> {
+//! # #[cfg(not(all(feature = "eh0", feature = "float")))] fn main() {}
+//! # #[cfg(all(feature = "eh0", feature = "float"))]
+//! fn main() -> Result<(), Mpu6886Error<LinuxI2CError>> {
 //!     let i2c = I2cdev::new("/dev/i2c-1")
-//!         .map_err(mpu6886Error::I2c)?;
+//!         .map_err(Mpu6886Error::I2c)?;
 //!
 //!     let mut delay = Delay;
-//!     let mut mpu = mpu6886::new(i2c);
+//!     let mut mpu = Mpu6886::new(i2c);
 //!
 //!     mpu.init(&mut delay)?;
 //!
@@ -32,7 +34,7 @@
 //!
 //!         // get sensor temp
 //!         let temp = mpu.get_temp()?;
-//!         printlnasd!("temp: {:?}c", temp);
+//!         println!("temp: {:?}c", temp);
 //!
 //!         // get gyro data, scaled with sensitivity
 //!         let gyro = mpu.get_gyro()?;
@@ -44,6 +46,54 @@
 //!     }
 //! }
 //! ```
+//!
+//! Over SPI the driver is created from the bus and the chip select pin instead,
+//! e.g. `Mpu6886::new_spi(spi, cs)`, all other methods work the same.
+//!
+//! ### embedded-hal versions
+//! `embedded-hal` 0.2 is supported with the default feature `eh0`, `embedded-hal` 1.0 with the
+//! feature `eh1`. Both can be enabled together, the version is inferred from the traits the
+//! bus implements. For buses implementing both versions, it has to be named explicitly, e.g.
+//! `Mpu6886::<interface::I2cInterface<_, interface::Eh1>>::new(i2c)`. Over SPI, 1.0 `SpiDevice`s are used with
+//! `Mpu6886::new_spi_device(spi)`.
+//!
+//! ### async
+//! With the feature `async`, [`Mpu6886Async`] offers the same driver on top of
+//! `embedded-hal-async` i2c buses and delays, e.g. for Embassy.
+//!
+//! ### Integer only readings
+//! `get_raw` reads accel, temp and gyro counts in one burst as [`RawSample`], which converts
+//! to milli-g, milli-dps and milli-degrees celcius without floating point, see [`raw`].
+//! Rates are set and read in mHz (`set_sample_rate_mhz`, `get_sample_rate_mhz`,
+//! `enter_low_power_accel_mhz`), so targets without FPU pull in no soft-float code.
+//!
+//! ### Floating point readings
+//! The scaled `f32` readings (`get_acc`, `get_gyro`, `read_all`, ...), rates in Hz, the FIFO
+//! sample stream, calibration, self-test and attitude estimation are enabled by the feature
+//! `float`. It is not a default feature, the driver builds with the integer API only unless
+//! `float` is enabled.
+//!
+//! ### Calibration
+//! `calibrate_gyro` measures the gyro bias at rest and writes it to the gyro offset
+//! registers. A six position accelerometer calibration yields a [`Calibration`] the driver
+//! applies to all scaled accel readings, see [`calibration`]. Calibrations encode into a
+//! checksummed blob for flash and are restored with `apply_calibration`, the feature `serde`
+//! adds serde support.
+//!
+//! ### Self-test
+//! `self_test` runs the factory self-test of gyro and accel and compares the response with
+//! the trim codes programmed in production, returning a [`SelfTestReport`], see [`selftest`].
+//!
+//! ### Attitude estimation
+//! [`ComplementaryFilter`] fuses gyro and accel readings into roll and pitch robust to
+//! vibration, plus a gyro integrated relative yaw. [`Madgwick`] and [`Mahony`] estimate the
+//! orientation as quaternion, optionally with an external magnetometer, see [`fusion`].
+//! [`Eskf`] is a Kalman filter for orientation and gyro bias, with noise from the datasheet at
+//! the configured bandwidths and the covariance exposed for health checks.
+//!
+//! ### Testing without a board
+//! The feature `sim` adds `sim::Mpu6886Sim`, a simulated register map the driver can talk
+//! to over i2c, spi or directly, while tests inject samples and wake on motion events.
 
 #![no_std]
 
@@ -51,17 +101,45 @@ mod bits;
 pub mod device;
 pub mod config;
 pub mod error;
+pub mod interface;
+pub mod fifo;
+pub mod raw;
+#[cfg(feature = "float")]
+pub mod calibration;
+#[cfg(feature = "float")]
+pub mod selftest;
+#[cfg(feature = "float")]
+pub mod fusion;
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(all(feature = "float", any(test, feature = "sim")))]
+pub mod sim;
 
 use crate::config::*;
 use crate::device::*;
-use crate::error::*;
-
+pub use crate::error::*;
+pub use crate::fifo::{FifoLayout, FifoMode};
+use crate::fifo::FifoState;
+#[cfg(feature = "float")]
+pub use crate::fifo::{FifoClock, FifoDrain, Sample, TimedSample};
+pub use crate::raw::RawSample;
+#[cfg(feature = "float")]
+pub use crate::raw::ImuSample;
+#[cfg(feature = "float")]
+pub use crate::calibration::{AccelOffset, Calibration, GyroOffset, TempCoefficients, TempDriftRecorder};
+#[cfg(feature = "float")]
+pub use crate::selftest::SelfTestReport;
+#[cfg(feature = "float")]
+pub use crate::fusion::{Ahrs, ComplementaryFilter, Eskf, Madgwick, Mahony};
+use crate::interface::*;
+#[cfg(feature = "async")]
+pub use crate::asynch::Mpu6886Async;
+
+#[cfg(feature = "float")]
 use libm::{powf, atan2f, sqrtf};
-use nalgebra::{Vector3, Vector2};
-use embedded_hal::{
-    blocking::delay::DelayMs,
-    blocking::i2c::{Write, WriteRead},
-};
+use nalgebra::Vector3;
+#[cfg(feature = "float")]
+use nalgebra::Vector2;
 //use esp_println::println;
 /// PI, f32
 pub const PI: f32 = core::f32::consts::PI;
@@ -80,64 +158,184 @@ pub const GRAVITY: f32 = 9.806651;
 //     InvalidChipId(u8),
 // }
 
+#[cfg(feature = "float")]
+/// Roll and pitch from accelerometer readings, see `get_acc_angles`
+pub(crate) fn acc_angles(acc: &Vector3<f32>) -> Vector2<f32> {
+    Vector2::<f32>::new(
+        atan2f(acc.y, sqrtf(powf(acc.x, 2.) + powf(acc.z, 2.))),
+        atan2f(-acc.x, sqrtf(powf(acc.y, 2.) + powf(acc.z, 2.)))
+    )
+}
+
+#[cfg(feature = "float")]
+/// Decodes one accel, temp, gyro FIFO packet, see `read_fifo`
+pub(crate) fn fifo_data(buf: &[u8; 14], acc_sensitivity: f32, gyro_sensitivity: f32) -> Vector3<Vector3<f32>> {
+    let raw = RawSample::from_bytes(buf);
+
+    Vector3::<Vector3<f32>>::new(
+        raw::accel_g(raw.accel, acc_sensitivity),
+        raw::gyro_dps(raw.gyro, gyro_sensitivity),
+        Vector3::new(raw::temp_deg_c(raw.temp), 0.0, 0.0),
+    )
+}
+
+#[cfg(feature = "float")]
+/// `read_fifo` data in m/s^2 and rad/s, see `read_fifo_si`
+pub(crate) fn fifo_data_si(mut data: Vector3<Vector3<f32>>) -> Vector3<Vector3<f32>> {
+    data[0] *= GRAVITY;
+    data[1] *= PI_180;
+    data
+}
+
+/// PWR_MGMT_1 written to wake the sensor: sleep off, all sensors on, auto selected PLL clock
+pub(crate) const WAKE_PWR_MGMT_1: u8 = CLKSEL::AUTOPLL1 as u8;
+
+/// Time in ms the sensor needs to wake up or reset
+pub(crate) const STARTUP_DELAY_MS: u8 = 100;
+
+/// Fails with `InvalidChipId` unless the WHOAMI value is the one of the mpu6886
+pub(crate) fn check_chip_id<E>(chip_type: u8) -> Result<(), Mpu6886Error<E>> {
+    if chip_type != CHIP_ID {
+        return Err(Mpu6886Error::InvalidChipId(chip_type));
+    }
+    Ok(())
+}
+
+/// SMPLRT_DIV closest to the rate in mHz, the rate must be within 1 kHz / 256 and 1 kHz
+fn sample_rate_div(rate_mhz: u32) -> Result<u8, SensorError> {
+    if !(INTERNAL_SAMPLE_RATE_MHZ.div_ceil(256)..=INTERNAL_SAMPLE_RATE_MHZ).contains(&rate_mhz) {
+        return Err(SensorError::InvalidSampleRate);
+    }
+    // 1 kHz / rate rounded, minus 1
+    Ok(((2 * INTERNAL_SAMPLE_RATE_MHZ + rate_mhz) / (2 * rate_mhz) - 1) as u8)
+}
+
+/// Output data rate in mHz for SMPLRT_DIV, rounded
+fn div_sample_rate_mhz(div: u8) -> u32 {
+    let divider = 1 + div as u32;
+    (INTERNAL_SAMPLE_RATE_MHZ + divider / 2) / divider
+}
+
+#[cfg(feature = "float")]
+/// Rate in Hz to mHz, negative rates are 0
+fn rate_mhz(rate: f32) -> u32 {
+    (rate * 1000.0 + 0.5) as u32
+}
+
+/// Registers changed by `enter_low_power_accel`, restored by `exit_low_power`
+#[derive(Clone, Copy, Debug)]
+struct PowerConfig {
+    pwr_mgmt_1: u8,
+    pwr_mgmt_2: u8,
+    smplrt_div: u8,
+    accel_config_2: u8,
+    lp_mode_cfg: u8,
+}
+
 /// Handles all operations on/with mpu6886
 pub struct Mpu6886<I> {
-    i2c: I,
-    slave_addr: u8,
+    iface: I,
+    // sensitivities and FIFO drain state are only read by the scaled readings
+    #[cfg_attr(not(feature = "float"), allow(dead_code))]
     acc_sensitivity: f32,
+    #[cfg_attr(not(feature = "float"), allow(dead_code))]
     gyro_sensitivity: f32,
+    /// full power configuration while in low power mode
+    full_power: Option<PowerConfig>,
+    /// axes not in standby, as last written or read
+    enabled_axes: Axes,
+    /// FIFO layout and drain state
+    fifo: FifoState,
+    /// correction of scaled accel readings
+    #[cfg(feature = "float")]
+    calibration: Option<Calibration>,
 }
 
-impl<I, E> Mpu6886<I>
-where
-    I: Write<Error = E> + WriteRead<Error = E>, 
-{
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+impl<I, H> Mpu6886<I2cInterface<I, H>> {
     /// Side effect free constructor with default sensitivies, no calibration
     pub fn new(i2c: I) -> Self {
-        Mpu6886 {
-            i2c,
-            slave_addr: DEFAULT_SLAVE_ADDR,
-            acc_sensitivity: ACCEL_SENS.0,
-            gyro_sensitivity: GYRO_SENS.0,
-        }
+        Self::new_with_addr(i2c, DEFAULT_SLAVE_ADDR)
     }
 
     /// custom sensitivity
     pub fn new_with_sens(i2c: I, arange: AccelRange, grange: GyroRange) -> Self {
-        Mpu6886 {
-            i2c,
-            slave_addr: DEFAULT_SLAVE_ADDR,
-            acc_sensitivity: arange.sensitivity(),
-            gyro_sensitivity: grange.sensitivity(),
-        }
+        Self::new_with_addr_and_sens(i2c, DEFAULT_SLAVE_ADDR, arange, grange)
     }
 
     /// Same as `new`, but the chip address can be specified (e.g. 0x69, if the A0 pin is pulled up)
     pub fn new_with_addr(i2c: I, slave_addr: u8) -> Self {
-        Mpu6886 {
-            i2c,
-            slave_addr,
-            acc_sensitivity: ACCEL_SENS.0,
-            gyro_sensitivity: GYRO_SENS.0,
-        }
+        Self::new_with_interface(I2cInterface::new(i2c, slave_addr))
     }
 
     /// Combination of `new_with_sens` and `new_with_addr`
     pub fn new_with_addr_and_sens(i2c: I, slave_addr: u8, arange: AccelRange, grange: GyroRange) -> Self {
+        Self::new_with_interface_and_sens(I2cInterface::new(i2c, slave_addr), arange, grange)
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<SPI, CS> Mpu6886<SpiInterface<SPI, CS>> {
+    /// Same as `new`, but talks to the chip over spi, selected by the cs pin
+    pub fn new_spi(spi: SPI, cs: CS) -> Self {
+        Self::new_with_interface(SpiInterface::new(spi, cs))
+    }
+
+    /// Combination of `new_spi` and `new_with_sens`
+    pub fn new_spi_with_sens(spi: SPI, cs: CS, arange: AccelRange, grange: GyroRange) -> Self {
+        Self::new_with_interface_and_sens(SpiInterface::new(spi, cs), arange, grange)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> Mpu6886<SpiDeviceInterface<SPI>> {
+    /// Same as `new`, but talks to the chip over an `embedded-hal` 1.0 spi device
+    pub fn new_spi_device(spi: SPI) -> Self {
+        Self::new_with_interface(SpiDeviceInterface::new(spi))
+    }
+
+    /// Combination of `new_spi_device` and `new_with_sens`
+    pub fn new_spi_device_with_sens(spi: SPI, arange: AccelRange, grange: GyroRange) -> Self {
+        Self::new_with_interface_and_sens(SpiDeviceInterface::new(spi), arange, grange)
+    }
+}
+
+impl<I> Mpu6886<I> {
+    /// Side effect free constructor on any bus interface, default sensitivities
+    pub fn new_with_interface(iface: I) -> Self {
+        Self::new_with_interface_and_sens(iface, AccelRange::G2, GyroRange::D250)
+    }
+
+    /// Combination of `new_with_interface` and `new_with_sens`
+    pub fn new_with_interface_and_sens(iface: I, arange: AccelRange, grange: GyroRange) -> Self {
         Mpu6886 {
-            i2c,
-            slave_addr,
+            iface,
             acc_sensitivity: arange.sensitivity(),
             gyro_sensitivity: grange.sensitivity(),
+            full_power: None,
+            enabled_axes: Axes::ALL,
+            fifo: FifoState::default(),
+            #[cfg(feature = "float")]
+            calibration: None,
         }
     }
 
+    /// Returns the bus interface, e.g. to share the bus with other devices
+    pub fn release(self) -> I {
+        self.iface
+    }
+}
+
+impl<I, E> Mpu6886<I>
+where
+    I: Interface<Error = E>,
+{
     /// Wakes mpu6886 with all sensors enabled (default)
-    fn wake<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+    fn wake<D: Delay<I::Hal>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
         // mpu6886 has sleep enabled by default -> set bit 0 to wake
         // Set clock source to be PLL with x-axis gyroscope reference, bits 2:0 = 001 (See Register Map )
-        self.write_byte(PWR_MGMT_1::ADDR, 0x01)?;
-        delay.delay_ms(100u8);
+        self.write_byte(PWR_MGMT_1::ADDR, WAKE_PWR_MGMT_1)?;
+        delay.delay_ms(STARTUP_DELAY_MS);
         Ok(())
     }
 
@@ -151,7 +349,7 @@ where
     /// (or  an  external  clocksource) as the clock reference for improved stability.
     /// The clock source can be selected according to the following table...."
     pub fn set_clock_source(&mut self, source: CLKSEL) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::CLKSEL.bit, PWR_MGMT_1::CLKSEL.length, source as u8)?)
+        self.write_bits(PWR_MGMT_1::ADDR, PWR_MGMT_1::CLKSEL.bit, PWR_MGMT_1::CLKSEL.length, source as u8)
     }
 
     /// get current clock source
@@ -161,7 +359,7 @@ where
     }
 
     /// Init wakes mpu6886 and verifies register addr, e.g. in i2c
-    pub fn init<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+    pub fn init<D: Delay<I::Hal>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
         self.wake(delay)?;
         self.verify()?;
         self.set_accel_range(AccelRange::G2)?;
@@ -172,35 +370,87 @@ where
     /// Verifies device to address 0x68 with WHOAMI.addr() Register
     fn verify(&mut self) -> Result<(), Mpu6886Error<E>> {
         let chip_type = self.read_byte(WHOAMI)?;
-        if chip_type != 0x19 {
-            return Err(Mpu6886Error::InvalidChipId(chip_type));
-        }
-        Ok(())
+        check_chip_id(chip_type)
     }
 
-    /// setup motion detection
-    /// sources:
-    /// * https://github.com/kriswiner/mpu6886/blob/a7e0c8ba61a56c5326b2bcd64bc81ab72ee4616b/mpu6886IMU.ino#L486
-    /// * https://arduino.stackexchange.com/a/48430
+    /// setup wake on motion with the default `WomConfig`
     pub fn setup_motion_detection(&mut self) -> Result<(), Mpu6886Error<E>> {
-        self.write_byte(0x6B, 0x00)?;
-        // optional? self.write_byte(0x68, 0x07)?; // Reset all internal signal paths in the MPU-6050 by writing 0x07 to register 0x68;
-        self.write_byte(INT_PIN_CFG::ADDR, 0x20)?; //write register 0x37 to select how to use the interrupt pin. For an active high, push-pull signal that stays until register (decimal) 58 is read, write 0x20.
-        self.write_byte(ACCEL_CONFIG::ADDR, 0x01)?; //Write register 28 (==0x1C) to set the Digital High Pass Filter, bits 3:0. For example set it to 0x01 for 5Hz. (These 3 bits are grey in the data sheet, but they are used! Leaving them 0 means the filter always outputs 0.)
-        self.write_byte(MOT_THR, 10)?; //Write the desired Motion threshold to register 0x1F (For example, write decimal 20).
-        self.write_byte(MOT_DUR, 40)?; //Set motion detect duration to 1  ms; LSB is 1 ms @ 1 kHz rate
-        self.write_byte(0x69, 0x15)?; //to register 0x69, write the motion detection decrement and a few other settings (for example write 0x15 to set both free-fall and motion decrements to 1 and accelerometer start-up delay to 5ms total by adding 1ms. )
-        self.write_byte(INT_ENABLE::ADDR, 0x40)?; //write register 0x38, bit 6 (0x40), to enable motion detection interrupt.
-        Ok(())
+        self.setup_wake_on_motion(&WomConfig::default())
+    }
+
+    /// Configures and enables wake on motion: thresholds, reference sample, axis logic,
+    /// WoM interrupts of all axes and the interrupt pin latch
+    ///
+    /// The accelerometer must be running, in low power mode wake on motion is evaluated at the
+    /// wake up rate. `WomMode::Initial` writes a value of `ACCEL_INTEL_MODE` the MPU6886
+    /// documents as not used, see there.
+    pub fn setup_wake_on_motion(&mut self, config: &WomConfig) -> Result<(), Mpu6886Error<E>> {
+        let max_mg = WOM_THR_LSB_MG * 255;
+        if config.threshold_mg.iter().any(|mg| *mg > max_mg) {
+            return Err(SensorError::InvalidWomThreshold.into());
+        }
+        for (reg, mg) in [ACCEL_WOM_X_THR, ACCEL_WOM_Y_THR, ACCEL_WOM_Z_THR].iter().zip(config.threshold_mg) {
+            self.write_byte(*reg, ((mg + WOM_THR_LSB_MG / 2) / WOM_THR_LSB_MG) as u8)?;
+        }
+
+        self.write_bit(INT_PIN_CFG::ADDR, INT_PIN_CFG::LATCH_INT_EN, config.latch == InterruptLatch::Latched)?;
+        let interrupts = self.get_enabled_interrupts()?;
+        self.set_interrupts_enabled(interrupts | Interrupts::WOM)?;
+
+        let mut intel_ctrl = self.read_byte(ACCEL_INTEL_CTRL::ADDR)?;
+        bits::set_bit(&mut intel_ctrl, ACCEL_INTEL_CTRL::ACCEL_INTEL_EN, true);
+        bits::set_bit(&mut intel_ctrl, ACCEL_INTEL_CTRL::ACCEL_INTEL_MODE, config.mode == WomMode::Previous);
+        bits::set_bit(&mut intel_ctrl, ACCEL_INTEL_CTRL::WOM_TH_MODE, config.logic == WomLogic::And);
+        self.write_byte(ACCEL_INTEL_CTRL::ADDR, intel_ctrl)
+    }
+
+    /// Disables the wake on motion logic and its interrupts
+    pub fn disable_wake_on_motion(&mut self) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(ACCEL_INTEL_CTRL::ADDR, ACCEL_INTEL_CTRL::ACCEL_INTEL_EN, false)?;
+        let interrupts = self.get_enabled_interrupts()?;
+        self.set_interrupts_enabled(interrupts & !Interrupts::WOM)
+    }
+
+    /// get axes which detected motion (WOM_X_INT, WOM_Y_INT, WOM_Z_INT of INT_STATUS)
+    ///
+    /// Reading INT_STATUS clears all interrupt status bits, use `get_interrupt_status`
+    /// if other interrupts are of interest too
+    pub fn get_wom_status(&mut self) -> Result<WomStatus, Mpu6886Error<E>> {
+        Ok(self.get_interrupt_status()?.wom)
+    }
+
+    /// Configures the interrupt pin (INT_PIN_CFG), FSYNC settings are kept
+    pub fn set_interrupt_pin_config(&mut self, config: InterruptPinConfig) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(INT_PIN_CFG::ADDR, INT_PIN_CFG::INT_LEVEL, 4, config.bits() >> 4)
+    }
+
+    /// get interrupt pin configuration (INT_PIN_CFG)
+    pub fn get_interrupt_pin_config(&mut self) -> Result<InterruptPinConfig, Mpu6886Error<E>> {
+        Ok(InterruptPinConfig::from(self.read_byte(INT_PIN_CFG::ADDR)?))
+    }
+
+    /// Enables the given interrupt sources, all others are disabled (INT_ENABLE)
+    pub fn set_interrupts_enabled(&mut self, interrupts: Interrupts) -> Result<(), Mpu6886Error<E>> {
+        let mut int_enable = self.read_byte(INT_ENABLE::ADDR)?;
+        int_enable = (int_enable & !Interrupts::ALL.bits()) | interrupts.bits();
+        self.write_byte(INT_ENABLE::ADDR, int_enable)
+    }
+
+    /// get enabled interrupt sources (INT_ENABLE)
+    pub fn get_enabled_interrupts(&mut self) -> Result<Interrupts, Mpu6886Error<E>> {
+        Ok(Interrupts::from_bits(self.read_byte(INT_ENABLE::ADDR)?))
+    }
+
+    /// get pending interrupts, decoded from one read of INT_STATUS, which clears it
+    pub fn get_interrupt_status(&mut self) -> Result<InterruptStatus, Mpu6886Error<E>> {
+        Ok(InterruptStatus::from(self.read_byte(INT_STATUS::ADDR)?))
     }
 
     /// get whether or not WOM has been detected (INT_STATUS) one of (WOM_X_INT, WOM_Y_INT, WOM_Z_INT)
     pub fn get_motion_detected(&mut self) -> Result<bool, Mpu6886Error<E>> {
-        let mask = INT_STATUS::WOM_X_INT | INT_STATUS::WOM_Y_INT | INT_STATUS::WOM_Z_INT;
-        Ok(self.read_bit(INT_STATUS::ADDR, mask)? != 0)
+        Ok(self.get_wom_status()?.any())
     }
 
-
     /// Set gyro range, and update sensitivity accordingly
     pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), Mpu6886Error<E>> {
         self.write_bits(GYRO_CONFIG::ADDR,
@@ -242,16 +492,18 @@ where
     }
 
     /// reset device
-    pub fn reset_device<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
+    pub fn reset_device<D: Delay<I::Hal>>(&mut self, delay: &mut D) -> Result<(), Mpu6886Error<E>> {
         self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::DEVICE_RESET, true)?;
-        delay.delay_ms(100u8);
+        delay.delay_ms(STARTUP_DELAY_MS);
+        self.enabled_axes = Axes::ALL;
+        self.fifo.device_reset();
         // Note: Reset sets sleep to true! Section register map: resets PWR_MGMT to 0x40
         Ok(())
     }
 
     /// enable, disable sleep of sensor
     pub fn set_sleep_enabled(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, enable)?)
+        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::SLEEP, enable)
     }
 
     /// get sleep status
@@ -263,7 +515,7 @@ where
     /// TEMP_DIS actually saves "disabled status"
     /// 1 is disabled! -> enable=true : bit=!enable
     pub fn set_temp_enabled(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS, !enable)?)
+        self.write_bit(PWR_MGMT_1::ADDR, PWR_MGMT_1::TEMP_DIS, !enable)
     }
 
     /// get temperature sensor status
@@ -275,7 +527,7 @@ where
 
     /// set accel x self test
     pub fn set_accel_x_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::XA_ST, enable)?)
+        self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::XA_ST, enable)
     }
 
     /// get accel x self test
@@ -285,7 +537,7 @@ where
 
     /// set accel y self test
     pub fn set_accel_y_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::YA_ST, enable)?)
+        self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::YA_ST, enable)
     }
 
     /// get accel y self test
@@ -295,7 +547,7 @@ where
 
     /// set accel z self test
     pub fn set_accel_z_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
-        Ok(self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::ZA_ST, enable)?)
+        self.write_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::ZA_ST, enable)
     }
 
     /// get accel z self test
@@ -303,166 +555,418 @@ where
         Ok(self.read_bit(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::ZA_ST)? != 0)
     }
 
+    /// set gyro x self test
+    pub fn set_gyro_x_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::XG_ST, enable)
+    }
+
+    /// get gyro x self test
+    pub fn get_gyro_x_self_test(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        Ok(self.read_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::XG_ST)? != 0)
+    }
+
+    /// set gyro y self test
+    pub fn set_gyro_y_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::YG_ST, enable)
+    }
+
+    /// get gyro y self test
+    pub fn get_gyro_y_self_test(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        Ok(self.read_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::YG_ST)? != 0)
+    }
+
+    /// set gyro z self test
+    pub fn set_gyro_z_self_test(&mut self, enable: bool) -> Result<(), Mpu6886Error<E>> {
+        self.write_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::ZG_ST, enable)
+    }
+
+    /// get gyro z self test
+    pub fn get_gyro_z_self_test(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        Ok(self.read_bit(GYRO_CONFIG::ADDR, GYRO_CONFIG::ZG_ST)? != 0)
+    }
+
+    #[cfg(feature = "float")]
     /// Roll and pitch estimation from raw accelerometer readings
     /// NOTE: no yaw! no magnetometer present on mpu6886
     /// https://www.nxp.com/docs/en/application-note/AN3461.pdf equation 28, 29
     pub fn get_acc_angles(&mut self) -> Result<Vector2<f32>, Mpu6886Error<E>> {
         let acc = self.get_acc()?;
 
-        Ok(Vector2::<f32>::new(
-            atan2f(acc.y, sqrtf(powf(acc.x, 2.) + powf(acc.z, 2.))),
-            atan2f(-acc.x, sqrtf(powf(acc.y, 2.) + powf(acc.z, 2.)))
-        ))
+        Ok(acc_angles(&acc))
     }
 
+    /// get accel filter bandwidth (`ACCEL_FCHOICE_B` and `A_DLPF_CFG` of ACCEL_CONFIG_2)
     pub fn get_accel_bandwith(&mut self) -> Result<AccelBw, Mpu6886Error<E>> {
-        // `ACCEL_UI_FILT_BW` occupies bits 2:0 in the register
-        let bw_sel = self.read_bits(ACCEL_CONFIG_2::ADDR, 3, 4)?;
+        let bw_sel = self.read_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_BW.bit, ACCEL_CONFIG_2::ACCEL_BW.length)?;
         let bw = AccelBw::try_from(bw_sel)?;
 
         Ok(bw)
     }
 
+    /// set accel filter bandwidth (`ACCEL_FCHOICE_B` and `A_DLPF_CFG` of ACCEL_CONFIG_2),
+    /// other bits of the register are kept
     pub fn set_accel_bw(&mut self, bw: AccelBw) -> Result<(), Mpu6886Error<E>> {
-        // TODO: modify register if DEC2_CFG needs to be set elsewhere
+        self.write_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_BW.bit, ACCEL_CONFIG_2::ACCEL_BW.length, bw.bits())
+    }
 
-        self.write_byte(CONFIG::ADDR, bw.bits())?;
-        self.write_byte(GYRO_CONFIG::ADDR, bw.bits())?;
-        
-        Ok(())
+    /// get accel averaging filter of low power mode (`DEC2_CFG` of ACCEL_CONFIG_2)
+    pub fn get_accel_averaging(&mut self) -> Result<AccelAveraging, Mpu6886Error<E>> {
+        let avg = self.read_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::DEC2_CFG.bit, ACCEL_CONFIG_2::DEC2_CFG.length)?;
+        Ok(AccelAveraging::from(avg))
     }
 
+    /// set accel averaging filter of low power mode (`DEC2_CFG` of ACCEL_CONFIG_2)
+    pub fn set_accel_averaging(&mut self, avg: AccelAveraging) -> Result<(), Mpu6886Error<E>> {
+        self.write_bits(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::DEC2_CFG.bit, ACCEL_CONFIG_2::DEC2_CFG.length, avg as u8)
+    }
+
+    /// get gyro filter bandwidth (`DLPF_CFG` of CONFIG and `FCHOICE_B` of GYRO_CONFIG)
     pub fn get_gyro_bandwith(&mut self) -> Result<GyroBw, Mpu6886Error<E>> {
-        // `DLPF_CFG` occupies bits 2:0 in the register of CONFIGURATION
-        let bw_sel = self.read_bits(CONFIG::ADDR, 2, 3)?;
-        let fchoice_b = self.read_bits(GYRO_CONFIG::ADDR, 1, 2)?;
-        let bw = GyroBw::try_from(bw_sel | (fchoice_b << 3))?;
+        let dlpf_cfg = self.read_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length)?;
+        let fchoice_b = self.read_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length)?;
+        let bw = GyroBw::from_fields(dlpf_cfg, fchoice_b)?;
 
         Ok(bw)
     }
 
+    /// set gyro filter bandwidth (`DLPF_CFG` of CONFIG and `FCHOICE_B` of GYRO_CONFIG),
+    /// other bits of the registers (FIFO_MODE, FS_SEL, self test) are kept
     pub fn set_gyro_bw(&mut self, bw: GyroBw) -> Result<(), Mpu6886Error<E>> {
-        // TODO: modify register if DEC2_CFG needs to be set elsewhere
-        //self.write_byte(ACCEL_CONFIG_2::ADDR, bw.bits())?;
-        
-        Ok(())
+        self.write_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length, bw.dlpf_cfg())?;
+        self.write_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length, bw.fchoice_b())
+    }
+
+    /// Sets the output data rate of the data registers and the FIFO with SMPLRT_DIV, in mHz
+    ///
+    /// ODR = 1 kHz / (1 + SMPLRT_DIV), the divider closest to rate_mhz is chosen and the
+    /// resulting rate in mHz is returned. SMPLRT_DIV only has an effect if both gyro and accel
+    /// low pass filters are enabled: FCHOICE_B = 0, 0 < DLPF_CFG < 7, ACCEL_FCHOICE_B = 0
+    pub fn set_sample_rate_mhz(&mut self, rate_mhz: u32) -> Result<u32, Mpu6886Error<E>> {
+        let div = sample_rate_div(rate_mhz)?;
+        if !self.sample_rate_div_effective()? {
+            return Err(SensorError::SampleRateDivIneffective.into());
+        }
+
+        self.write_byte(SMPLRT_DIV, div)?;
+        Ok(div_sample_rate_mhz(div))
     }
 
-    /// Converts 2 bytes number in 2 compliment
-    /// TODO i16?! whats 0x8000?!
-    fn read_word_2c(&self, byte: &[u8]) -> i32 {
-        let high: i32 = byte[0] as i32;
-        let low: i32 = byte[1] as i32;
-        let mut word: i32 = (high << 8) + low;
+    #[cfg(feature = "float")]
+    /// Same as `set_sample_rate_mhz` with the rates in Hz
+    pub fn set_sample_rate_hz(&mut self, rate: f32) -> Result<f32, Mpu6886Error<E>> {
+        Ok(self.set_sample_rate_mhz(rate_mhz(rate))? as f32 / 1000.0)
+    }
+
+    /// get current output data rate of data registers and FIFO in mHz
+    ///
+    /// Without active gyro low pass filter, the rate is given by the filter mode (8 or 32 kHz).
+    /// With bypassed accel low pass filter (ACCEL_FCHOICE_B, `AccelBw::Hz1046`) the accel runs
+    /// at 4 kHz, SMPLRT_DIV is ignored and the faster of the accel and gyro rate is returned.
+    pub fn get_sample_rate_mhz(&mut self) -> Result<u32, Mpu6886Error<E>> {
+        let fchoice_b = self.read_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length)?;
+        let dlpf_cfg = self.read_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length)?;
+        let accel_fchoice_b = self.read_bit(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_FCHOICE_B)?;
+
+        match (fchoice_b, dlpf_cfg, accel_fchoice_b) {
+            (0, 1..=6, 0) => Ok(div_sample_rate_mhz(self.read_byte(SMPLRT_DIV)?)),
+            (0, 1..=6, _) => Ok(ACCEL_BYPASS_SAMPLE_RATE_MHZ),
+            (0, _, _) => Ok(8_000_000),
+            _ => Ok(32_000_000),
+        }
+    }
+
+    #[cfg(feature = "float")]
+    /// Same as `get_sample_rate_mhz` in Hz
+    pub fn get_sample_rate(&mut self) -> Result<f32, Mpu6886Error<E>> {
+        Ok(self.get_sample_rate_mhz()? as f32 / 1000.0)
+    }
+
+    /// whether SMPLRT_DIV applies with the current gyro and accel filter configuration
+    fn sample_rate_div_effective(&mut self) -> Result<bool, Mpu6886Error<E>> {
+        let fchoice_b = self.read_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::FCHOICE_B.bit, GYRO_CONFIG::FCHOICE_B.length)?;
+        let dlpf_cfg = self.read_bits(CONFIG::ADDR, CONFIG::DLPF_CFG.bit, CONFIG::DLPF_CFG.length)?;
+        let accel_fchoice_b = self.read_bit(ACCEL_CONFIG_2::ADDR, ACCEL_CONFIG_2::ACCEL_FCHOICE_B)?;
 
-        if word >= 0x8000 {
-            word = -((65535 - word) + 1);
+        Ok(fchoice_b == 0 && (1..=6).contains(&dlpf_cfg) && accel_fchoice_b == 0)
+    }
+
+    /// Puts the mpu6886 into accelerometer only low power mode
+    ///
+    /// The gyro is switched off (STBY_XG, STBY_YG, STBY_ZG, no gyro cycle), the accelerometer
+    /// wakes up at rate_mhz (1 kHz / (1 + SMPLRT_DIV), 3.9 Hz to 1 kHz, in mHz) for a measurement
+    /// averaged over averaging samples (DEC2_CFG) and sleeps in between (CYCLE). GYRO_STANDBY is
+    /// cleared, it would keep the gyro drive running. The current configuration is saved for
+    /// `exit_low_power`, the reached rate in mHz is returned.
+    pub fn enter_low_power_accel_mhz(&mut self, rate_mhz: u32, averaging: AccelAveraging) -> Result<u32, Mpu6886Error<E>> {
+        let div = sample_rate_div(rate_mhz)?;
+
+        let saved = PowerConfig {
+            pwr_mgmt_1: self.read_byte(PWR_MGMT_1::ADDR)?,
+            pwr_mgmt_2: self.read_byte(PWR_MGMT_2::ADDR)?,
+            smplrt_div: self.read_byte(SMPLRT_DIV)?,
+            accel_config_2: self.read_byte(ACCEL_CONFIG_2::ADDR)?,
+            lp_mode_cfg: self.read_byte(LP_MODE_CFG::ADDR)?,
+        };
+        // entering twice keeps the full power configuration
+        self.full_power.get_or_insert(saved);
+
+        self.write_byte(SMPLRT_DIV, div)?;
+
+        let mut accel_config_2 = saved.accel_config_2;
+        bits::set_bit(&mut accel_config_2, ACCEL_CONFIG_2::ACCEL_FCHOICE_B, false);
+        bits::set_bits(&mut accel_config_2, ACCEL_CONFIG_2::DEC2_CFG.bit, ACCEL_CONFIG_2::DEC2_CFG.length, averaging as u8);
+        self.write_byte(ACCEL_CONFIG_2::ADDR, accel_config_2)?;
+
+        self.write_bit(LP_MODE_CFG::ADDR, LP_MODE_CFG::GYRO_CYCLE, false)?;
+        let mut pwr_mgmt_2 = saved.pwr_mgmt_2;
+        for bit in [PWR_MGMT_2::STBY_XA, PWR_MGMT_2::STBY_YA, PWR_MGMT_2::STBY_ZA] {
+            bits::set_bit(&mut pwr_mgmt_2, bit, false);
         }
+        for bit in [PWR_MGMT_2::STBY_XG, PWR_MGMT_2::STBY_YG, PWR_MGMT_2::STBY_ZG] {
+            bits::set_bit(&mut pwr_mgmt_2, bit, true);
+        }
+        self.write_byte(PWR_MGMT_2::ADDR, pwr_mgmt_2)?;
+        self.enabled_axes = Axes::from_standby_bits(pwr_mgmt_2);
+
+        let mut pwr_mgmt_1 = saved.pwr_mgmt_1;
+        bits::set_bit(&mut pwr_mgmt_1, PWR_MGMT_1::SLEEP, false);
+        bits::set_bit(&mut pwr_mgmt_1, PWR_MGMT_1::GYRO_STANDBY, false);
+        bits::set_bit(&mut pwr_mgmt_1, PWR_MGMT_1::CYCLE, true);
+        self.write_byte(PWR_MGMT_1::ADDR, pwr_mgmt_1)?;
+
+        Ok(div_sample_rate_mhz(div))
+    }
+
+    #[cfg(feature = "float")]
+    /// Same as `enter_low_power_accel_mhz` with the rates in Hz
+    pub fn enter_low_power_accel(&mut self, rate: f32, averaging: AccelAveraging) -> Result<f32, Mpu6886Error<E>> {
+        Ok(self.enter_low_power_accel_mhz(rate_mhz(rate), averaging)? as f32 / 1000.0)
+    }
 
-        word
+    /// Leaves the low power mode, restoring the configuration from before `enter_low_power_accel`
+    ///
+    /// Does nothing, if not in low power mode
+    pub fn exit_low_power(&mut self) -> Result<(), Mpu6886Error<E>> {
+        let saved = match self.full_power {
+            Some(saved) => saved,
+            None => return Ok(()),
+        };
+        // leave cycle mode first
+        self.write_byte(PWR_MGMT_1::ADDR, saved.pwr_mgmt_1)?;
+        self.write_byte(PWR_MGMT_2::ADDR, saved.pwr_mgmt_2)?;
+        self.enabled_axes = Axes::from_standby_bits(saved.pwr_mgmt_2);
+        self.write_byte(LP_MODE_CFG::ADDR, saved.lp_mode_cfg)?;
+        self.write_byte(ACCEL_CONFIG_2::ADDR, saved.accel_config_2)?;
+        self.write_byte(SMPLRT_DIV, saved.smplrt_div)?;
+        self.full_power = None;
+        Ok(())
     }
 
+    /// whether the driver put the mpu6886 into low power mode
+    pub fn is_low_power(&self) -> bool {
+        self.full_power.is_some()
+    }
 
+    /// Enables the given accel and gyro axes, all others are put in standby (PWR_MGMT_2),
+    /// with a single register write
+    pub fn set_enabled_axes(&mut self, axes: Axes) -> Result<(), Mpu6886Error<E>> {
+        let mut pwr_mgmt_2 = self.read_byte(PWR_MGMT_2::ADDR)?;
+        bits::set_bits(&mut pwr_mgmt_2, PWR_MGMT_2::STBY_XA, 6, axes.standby_bits());
+        self.write_byte(PWR_MGMT_2::ADDR, pwr_mgmt_2)?;
+        self.enabled_axes = axes;
+        Ok(())
+    }
+
+    /// get accel and gyro axes not in standby (PWR_MGMT_2)
+    pub fn get_enabled_axes(&mut self) -> Result<Axes, Mpu6886Error<E>> {
+        let pwr_mgmt_2 = self.read_byte(PWR_MGMT_2::ADDR)?;
+        self.enabled_axes = Axes::from_standby_bits(pwr_mgmt_2);
+        Ok(self.enabled_axes)
+    }
 
-    /// Reads rotation (gyro/acc) from specified register
-    fn read_rot(&mut self, reg: u8) -> Result<Vector3<f32>, Mpu6886Error<E>> {
+    /// Reads raw counts of the x, y, z registers starting at reg
+    fn read_rot_raw(&mut self, reg: u8) -> Result<Vector3<i16>, Mpu6886Error<E>> {
         let mut buf: [u8; 6] = [0; 6];
         self.read_bytes(reg, &mut buf)?;
+        Ok(raw::vector(&buf))
+    }
 
-        Ok(Vector3::<f32>::new(
-            self.read_word_2c(&buf[0..2]) as f32,
-            self.read_word_2c(&buf[2..4]) as f32,
-            self.read_word_2c(&buf[4..6]) as f32
-        ))
+    /// Accel, temp and gyro counts in one 14 byte burst from ACCEL_XOUT_H, see `RawSample`
+    ///
+    /// All values belong to the same sample, unlike separate `get_acc` and `get_gyro` calls.
+    pub fn get_raw(&mut self) -> Result<RawSample, Mpu6886Error<E>> {
+        let mut buf = [0; raw::RAW_SAMPLE_SIZE];
+        self.read_bytes(ACC_REGX_H, &mut buf)?;
+        Ok(RawSample::from_bytes(&buf))
+    }
+
+    #[cfg(feature = "float")]
+    /// Accel in g, gyro in rad/s and temp in degrees celcius of the same sample instant
+    ///
+    /// ACCEL_XOUT_H to GYRO_ZOUT_L are read in one burst, unlike separate `get_acc`,
+    /// `get_gyro` and `get_temp` calls.
+    pub fn read_all(&mut self) -> Result<ImuSample, Mpu6886Error<E>> {
+        let raw = self.get_raw()?;
+        let mut sample = ImuSample::from_raw(&raw, self.acc_sensitivity, self.gyro_sensitivity);
+        sample.accel = self.correct_acc(sample.accel, Some(sample.temp));
+        sample.gyro = self.correct_gyro(sample.gyro, Some(sample.temp));
+        Ok(sample)
     }
 
+    /// Accelerometer counts
+    pub fn get_acc_raw(&mut self) -> Result<Vector3<i16>, Mpu6886Error<E>> {
+        self.read_rot_raw(ACC_REGX_H)
+    }
+
+    /// Gyro counts
+    pub fn get_gyro_raw(&mut self) -> Result<Vector3<i16>, Mpu6886Error<E>> {
+        self.read_rot_raw(GYRO_REGX_H)
+    }
+
+    /// Temperature counts
+    pub fn get_temp_raw(&mut self) -> Result<i16, Mpu6886Error<E>> {
+        let mut buf: [u8; 2] = [0; 2];
+        self.read_bytes(TEMP_OUT_H, &mut buf)?;
+        Ok(raw::temp(&buf))
+    }
+
+    #[cfg(feature = "float")]
+    /// Applies the accel calibration, if set, with temperature compensation if temp is known
+    pub(crate) fn correct_acc(&self, acc: Vector3<f32>, temp: Option<f32>) -> Vector3<f32> {
+        match &self.calibration {
+            Some(calibration) => {
+                let drift = temp.map_or(Vector3::zeros(), |t| calibration.temp.accel_drift(t));
+                calibration.apply_accel(acc - drift)
+            }
+            None => acc,
+        }
+    }
+
+    #[cfg(feature = "float")]
+    /// Applies the gyro calibration, if set, with temperature compensation if temp is known
+    pub(crate) fn correct_gyro(&self, gyro: Vector3<f32>, temp: Option<f32>) -> Vector3<f32> {
+        match &self.calibration {
+            Some(calibration) => {
+                let drift = temp.map_or(Vector3::zeros(), |t| calibration.temp.gyro_drift(t));
+                calibration.apply_gyro(gyro - drift)
+            }
+            None => gyro,
+        }
+    }
+
+    #[cfg(feature = "float")]
+    /// whether the calibration has a temperature drift model
+    pub(crate) fn temp_compensated(&self) -> bool {
+        self.calibration.is_some_and(|calibration| calibration.temp.has_drift())
+    }
+
+    #[cfg(feature = "float")]
     /// Accelerometer readings in g
+    ///
+    /// With temperature compensation, accel and temp are read in one burst, see `read_all`
     pub fn get_acc(&mut self) -> Result<Vector3<f32>, Mpu6886Error<E>> {
-        let mut acc = self.read_rot(ACC_REGX_H)?;
-        acc /= self.acc_sensitivity;
-
-        Ok(acc)
+        if self.temp_compensated() {
+            return Ok(self.read_all()?.accel);
+        }
+        let acc = raw::accel_g(self.get_acc_raw()?, self.acc_sensitivity);
+        Ok(self.correct_acc(acc, None))
     }
 
+    #[cfg(feature = "float")]
     /// Gyro readings in rad/s
+    ///
+    /// With temperature compensation, gyro and temp are read in one burst, see `read_all`
     pub fn get_gyro(&mut self) -> Result<Vector3<f32>, Mpu6886Error<E>> {
-        let mut gyro = self.read_rot(GYRO_REGX_H)?;
+        if self.temp_compensated() {
+            return Ok(self.read_all()?.gyro);
+        }
+        let gyro = raw::gyro_rad_s(self.get_gyro_raw()?, self.gyro_sensitivity);
+        Ok(self.correct_gyro(gyro, None))
+    }
 
-        gyro *= PI_180 / self.gyro_sensitivity;
+    #[cfg(feature = "float")]
+    /// Same as `get_acc`, axes in standby are None instead of stale data
+    ///
+    /// The enabled axes are known from the last `set_enabled_axes` or `get_enabled_axes`.
+    pub fn get_acc_checked(&mut self) -> Result<Vector3<Option<f32>>, Mpu6886Error<E>> {
+        Ok(self.enabled_axes.mask_accel(self.get_acc()?))
+    }
 
-        Ok(gyro)
+    #[cfg(feature = "float")]
+    /// Same as `get_gyro`, axes in standby are None instead of stale data
+    pub fn get_gyro_checked(&mut self) -> Result<Vector3<Option<f32>>, Mpu6886Error<E>> {
+        Ok(self.enabled_axes.mask_gyro(self.get_gyro()?))
     }
 
+    #[cfg(feature = "float")]
     /// Sensor Temp in degrees celcius
     pub fn get_temp(&mut self) -> Result<f32, Mpu6886Error<E>> {
-        let mut buf: [u8; 2] = [0; 2];
-        self.read_bytes(TEMP_OUT_H, &mut buf)?;
-        let raw_temp = self.read_word_2c(&buf[0..2]) as f32;
-
-        // let high: u16 = buf[0] as u16;
-        // let low: u16 = buf[1] as u16;
-        // let word = high << 8 | low;
-        // let raw_temp = word as f32;
-
         // According to revision 4.2
-        Ok((raw_temp / TEMP_SENSITIVITY) + TEMP_OFFSET)
+        Ok(raw::temp_deg_c(self.get_temp_raw()?))
     }
 
     /// enable writing data to the fifo output, this function must be called before
-    /// reading with read_fifo()
-    /// currently only enabling all data gyro and accel is supported by the fifo-read()
+    /// reading with read_fifo() or drain_fifo()
+    /// read_fifo() needs gyro and accel enabled, drain_fifo() decodes all `FifoLayout`s
     /// enabling gyro will also enabel temperature
     pub fn enable_fifo(&mut self, accel: bool, gyro: bool) -> Result<(), Mpu6886Error<E>> {
-        self.write_bit(FIFO_EN, 3, accel)?;
-        self.write_bit(FIFO_EN, 4, gyro)?;
-        self.write_bit(USER_CTRL, 0, true)?;  // reset signal path
-        self.write_bit(USER_CTRL, 2, true)?; // reset fifo path
-        self.write_bit(USER_CTRL, 6, true)?; // enable fifo
+        self.write_bit(FIFO_EN, fifo::ACCEL_FIFO_EN, accel)?;
+        self.write_bit(FIFO_EN, fifo::GYRO_FIFO_EN, gyro)?;
+        self.fifo.layout = FifoLayout::new(accel, gyro);
+        self.write_bit(USER_CTRL, fifo::SIG_COND_RST, true)?;  // reset signal path
+        self.reset_fifo()?; // reset fifo path
+        self.write_bit(USER_CTRL, fifo::FIFO_ENABLE, true)?; // enable fifo
         Ok(())
     }
 
+    #[cfg(feature = "float")]
     /// Read sensor data from FIFO in one go
-    /// currently only enabling all data gyro and accel is supported by the fifo-read()
+    /// needs gyro and accel enabled in the fifo, fails with `UnsupportedFifoLayout` otherwise
     /// Vector_0 contains accelerometer data in g 
     /// Vector_1 contains gyro data in °/sec
     /// Vector_2 contains temperature in first position rest 0
+    /// Fails with `NofFifoData`, if the FIFO count is less than a packet, see also `drain_fifo`
     #[inline(always)]
     pub fn read_fifo(&mut self)  -> Result<Vector3<Vector3<f32>>, Mpu6886Error<E>> {
+        self.expect_fifo_layout(FifoLayout::AccelTempGyro)?;
+        fifo::expect_packet(self.get_fifo_count()?)?;
         let mut buf: [u8; 14] = [0; 14];
         self.read_bytes(FIFO_R_W, &mut buf)?;
-        if buf[0] != 255 {
-            let ax = (self.read_word_2c(&buf[0..2]) as f32)/self.acc_sensitivity;
-            let ay = (self.read_word_2c(&buf[2..4]) as f32)/self.acc_sensitivity;
-            let az = (self.read_word_2c(&buf[4..6]) as f32)/self.acc_sensitivity;
-            let t = (self.read_word_2c(&buf[6..8]) as f32/TEMP_SENSITIVITY) + TEMP_OFFSET;
-            let gx = (self.read_word_2c(&buf[8..10]) as f32) / self.gyro_sensitivity;
-            let gy = (self.read_word_2c(&buf[10..12]) as f32) / self.gyro_sensitivity;
-            let gz = (self.read_word_2c(&buf[12..14]) as f32) / self.gyro_sensitivity;
-
-            Ok(Vector3::<Vector3<f32>>::new(
-                Vector3::new(ax,ay,az),
-                Vector3::new(gx,gy,gz),
-                Vector3::new(t,0.0,0.0),
-            ))
-        } else {
-            Err(Mpu6886Error::SensorError(SensorError::NofFifoData))
-        }
+        let mut data = fifo_data(&buf, self.acc_sensitivity, self.gyro_sensitivity);
+        data[0] = self.correct_acc(data[0], Some(data[2].x));
+        // gyro in °/s here, the calibration works in rad/s
+        data[1] = self.correct_gyro(data[1] * PI_180, Some(data[2].x)) / PI_180;
+        Ok(data)
+    }
+
+    /// Same as `read_fifo`, one accel, temp, gyro packet as raw counts
+    pub fn read_fifo_raw(&mut self) -> Result<RawSample, Mpu6886Error<E>> {
+        self.expect_fifo_layout(FifoLayout::AccelTempGyro)?;
+        fifo::expect_packet(self.get_fifo_count()?)?;
+        let mut buf = [0; raw::RAW_SAMPLE_SIZE];
+        self.read_bytes(FIFO_R_W, &mut buf)?;
+        Ok(RawSample::from_bytes(&buf))
     }
 
+    #[cfg(feature = "float")]
+    /// Same as `read_fifo`, axes in standby are None, temperature is in x of Vector_2
+    pub fn read_fifo_checked(&mut self) -> Result<Vector3<Vector3<Option<f32>>>, Mpu6886Error<E>> {
+        let data = self.read_fifo()?;
+        Ok(Vector3::new(
+            self.enabled_axes.mask_accel(data[0]),
+            self.enabled_axes.mask_gyro(data[1]),
+            Vector3::new(Some(data[2].x), None, None),
+        ))
+    }
+
+    #[cfg(feature = "float")]
     pub fn read_fifo_si(&mut self) -> Result<Vector3<Vector3<f32>>, Mpu6886Error<E>> {
-        let mut data = self.read_fifo()?;
-        data[0][0] = data[0][0] * GRAVITY;
-        data[0][1] = data[0][1] * GRAVITY;
-        data[0][2] = data[0][2] * GRAVITY;
-        data[1][0] = data[1][0] * PI_180;
-        data[1][1] = data[1][1] * PI_180;
-        data[1][2] = data[1][2] * PI_180;
-        Ok(data)
+        Ok(fifo_data_si(self.read_fifo()?))
     }
 
     /// Writes byte to register
     pub fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), Mpu6886Error<E>> {
-        self.i2c.write(self.slave_addr, &[reg, byte])
+        self.iface.write_register(reg, byte)
             .map_err(Mpu6886Error::I2c)?;
         // delay disabled for dev build
         // TODO: check effects with physical unit
@@ -475,7 +979,7 @@ where
         let mut byte: [u8; 1] = [0; 1];
         self.read_bytes(reg, &mut byte)?;
         bits::set_bit(&mut byte[0], bit_n, enable);
-        Ok(self.write_byte(reg, byte[0])?)
+        self.write_byte(reg, byte[0])
     }
 
     /// Write bits data at reg from start_bit to start_bit+length
@@ -483,7 +987,7 @@ where
         let mut byte: [u8; 1] = [0; 1];
         self.read_bytes(reg, &mut byte)?;
         bits::set_bits(&mut byte[0], start_bit, length, data);
-        Ok(self.write_byte(reg, byte[0])?)
+        self.write_byte(reg, byte[0])
     }
 
     /// Read bit n from register
@@ -503,16 +1007,685 @@ where
     /// Reads byte from register
     pub fn read_byte(&mut self, reg: u8) -> Result<u8, Mpu6886Error<E>> {
         let mut byte: [u8; 1] = [0; 1];
-        self.i2c.write_read(self.slave_addr, &[reg], &mut byte)
-            .map_err(Mpu6886Error::I2c)?;
+        self.read_bytes(reg, &mut byte)?;
         Ok(byte[0])
     }
 
     /// Reads series of bytes into buf from specified reg
     pub fn read_bytes(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), Mpu6886Error<E>> {
-        self.i2c.write_read(self.slave_addr, &[reg], buf)
+        self.iface.read_registers(reg, buf)
             .map_err(Mpu6886Error::I2c)?;
         Ok(())
     }
 }
 
+#[cfg(all(test, any(feature = "eh0", feature = "eh1")))]
+mod tests {
+    /// Driver tests against the i2c mock of one embedded-hal version
+    macro_rules! hal_tests {
+        ($hal:ident) => {
+            mod $hal {
+                extern crate std;
+                use crate::*;
+                use std::vec;
+                use std::vec::Vec;
+                use embedded_hal_mock::$hal::delay::NoopDelay;
+                use embedded_hal_mock::$hal::i2c::{Mock, Transaction};
+
+                const ADDR: u8 = DEFAULT_SLAVE_ADDR;
+
+                /// read-modify-write of a single register
+                fn rmw(reg: u8, old: u8, new: u8) -> Vec<Transaction> {
+                    vec![
+                        Transaction::write_read(ADDR, vec![reg], vec![old]),
+                        Transaction::write(ADDR, vec![reg, new]),
+                    ]
+                }
+
+                #[test]
+                fn init() {
+                    let mut expectations = vec![
+                        Transaction::write(ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+                        Transaction::write_read(ADDR, vec![WHOAMI], vec![0x19]),
+                    ];
+                    expectations.extend(rmw(ACCEL_CONFIG::ADDR, 0x18, 0x00));
+                    expectations.extend(rmw(GYRO_CONFIG::ADDR, 0x18, 0x00));
+                    let mut i2c = Mock::new(&expectations);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    mpu.init(&mut NoopDelay::new()).unwrap();
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn init_rejects_wrong_chip() {
+                    let mut i2c = Mock::new(&[
+                        Transaction::write(ADDR, vec![PWR_MGMT_1::ADDR, 0x01]),
+                        Transaction::write_read(ADDR, vec![WHOAMI], vec![0x68]),
+                    ]);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    assert!(matches!(mpu.init(&mut NoopDelay::new()), Err(Mpu6886Error::InvalidChipId(0x68))));
+
+                    i2c.done();
+                }
+
+                #[cfg(feature = "float")]
+                #[test]
+                fn gyro_range_updates_sensitivity() {
+                    let mut expectations = rmw(GYRO_CONFIG::ADDR, 0x00, 0x18);
+                    // 1 dps at +-2000 dps: 16.4 LSB
+                    expectations.push(Transaction::write_read(ADDR, vec![GYRO_REGX_H], vec![0x00, 0x10, 0xff, 0xf0, 0x00, 0x00]));
+                    let mut i2c = Mock::new(&expectations);
+
+                    let mut mpu = Mpu6886::new_with_addr(i2c.clone(), ADDR);
+                    mpu.set_gyro_range(GyroRange::D2000).unwrap();
+                    let gyro = mpu.get_gyro().unwrap();
+                    assert!((gyro.x - 16.0 / 16.4 * PI_180).abs() < 1e-6);
+                    assert!((gyro.y + 16.0 / 16.4 * PI_180).abs() < 1e-6);
+                    assert_eq!(gyro.z, 0.0);
+
+                    i2c.done();
+                }
+
+                #[cfg(feature = "float")]
+                #[test]
+                fn acc_scaled_with_sensitivity() {
+                    let mut i2c = Mock::new(&[
+                        Transaction::write_read(ADDR, vec![ACC_REGX_H], vec![0x40, 0x00, 0xc0, 0x00, 0x20, 0x00]),
+                    ]);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    let acc = mpu.get_acc().unwrap();
+                    assert_eq!((acc.x, acc.y, acc.z), (1.0, -1.0, 0.5));
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn gyro_bw_round_trip() {
+                    use GyroBw::*;
+
+                    let all = [Hz8173, Hz3281Bypass, Hz250, Hz176, Hz92, Hz41, Hz20, Hz10, Hz5, Hz3281];
+                    // FIFO_MODE set, FS_SEL 2000 dps and x self test enabled, must survive
+                    let (config, gyro_config) = (0x40, 0x98);
+                    let mut expectations = Vec::new();
+                    for bw in all {
+                        let new_config = config | (bw as u8 & 0b111);
+                        let new_gyro_config = gyro_config | (bw as u8 >> 3);
+                        expectations.extend(rmw(CONFIG::ADDR, config | 0b101, new_config));
+                        expectations.extend(rmw(GYRO_CONFIG::ADDR, gyro_config | 0b11, new_gyro_config));
+                        expectations.push(Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![new_config]));
+                        expectations.push(Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![new_gyro_config]));
+                    }
+                    let mut i2c = Mock::new(&expectations);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    for bw in all {
+                        mpu.set_gyro_bw(bw).unwrap();
+                        assert_eq!(mpu.get_gyro_bandwith().unwrap(), bw);
+                    }
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn accel_bw_round_trip() {
+                    use AccelBw::*;
+
+                    let all = [Hz1046, Hz218, Hz99, Hz45, Hz21, Hz10, Hz5, Hz420];
+                    // DEC2_CFG 32 samples, must survive
+                    let accel_config_2 = 0x30;
+                    let mut expectations = Vec::new();
+                    for bw in all {
+                        let new = accel_config_2 | bw as u8;
+                        expectations.extend(rmw(ACCEL_CONFIG_2::ADDR, accel_config_2 | 0b1111, new));
+                        expectations.push(Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![new]));
+                    }
+                    expectations.extend(rmw(ACCEL_CONFIG_2::ADDR, 0x0f, 0x1f));
+                    expectations.push(Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x1f]));
+                    let mut i2c = Mock::new(&expectations);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    for bw in all {
+                        mpu.set_accel_bw(bw).unwrap();
+                        assert_eq!(mpu.get_accel_bandwith().unwrap(), bw);
+                    }
+                    mpu.set_accel_averaging(AccelAveraging::X8).unwrap();
+                    assert_eq!(mpu.get_accel_averaging().unwrap(), AccelAveraging::X8);
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn sample_rate() {
+                    let mut i2c = Mock::new(&[
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x18]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x01]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                        Transaction::write(ADDR, vec![SMPLRT_DIV, 9]),
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x18]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x01]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                        Transaction::write_read(ADDR, vec![SMPLRT_DIV], vec![9]),
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x00]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x01]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                        Transaction::write(ADDR, vec![SMPLRT_DIV, 2]),
+                    ]);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    assert_eq!(mpu.set_sample_rate_mhz(100_000).unwrap(), 100_000);
+                    assert_eq!(mpu.get_sample_rate_mhz().unwrap(), 100_000);
+                    // closest divider: 1 kHz / 3
+                    assert_eq!(mpu.set_sample_rate_mhz(300_000).unwrap(), 333_333);
+                    assert!(matches!(mpu.set_sample_rate_mhz(2_000_000),
+                                     Err(Mpu6886Error::SensorError(SensorError::InvalidSampleRate))));
+                    // 3.90625 Hz is the slowest rate
+                    assert!(matches!(mpu.set_sample_rate_mhz(3_906),
+                                     Err(Mpu6886Error::SensorError(SensorError::InvalidSampleRate))));
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn sample_rate_div_ineffective() {
+                    let mut i2c = Mock::new(&[
+                        // DLPF_CFG 7
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x00]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x07]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                        // FCHOICE_B != 0
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x02]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x01]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                        Transaction::write_read(ADDR, vec![GYRO_CONFIG::ADDR], vec![0x02]),
+                        Transaction::write_read(ADDR, vec![CONFIG::ADDR], vec![0x01]),
+                        Transaction::write_read(ADDR, vec![ACCEL_CONFIG_2::ADDR], vec![0x00]),
+                    ]);
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    assert!(matches!(mpu.set_sample_rate_mhz(100_000),
+                                     Err(Mpu6886Error::SensorError(SensorError::SampleRateDivIneffective))));
+                    assert!(matches!(mpu.set_sample_rate_mhz(100_000),
+                                     Err(Mpu6886Error::SensorError(SensorError::SampleRateDivIneffective))));
+                    assert_eq!(mpu.get_sample_rate_mhz().unwrap(), 32_000_000);
+
+                    i2c.done();
+                }
+
+                #[test]
+                fn reset_device() {
+                    let mut i2c = Mock::new(&rmw(PWR_MGMT_1::ADDR, 0x01, 0x81));
+
+                    let mut mpu = Mpu6886::new(i2c.clone());
+                    mpu.reset_device(&mut NoopDelay::new()).unwrap();
+
+                    i2c.done();
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "eh0")]
+    hal_tests!(eh0);
+    #[cfg(feature = "eh1")]
+    hal_tests!(eh1);
+}
+
+/// Driver tests against the register map simulator
+#[cfg(all(test, feature = "float"))]
+mod sim_tests {
+    use crate::*;
+    use crate::sim::{Mpu6886Sim, SimDelay, SimInterface};
+
+    fn setup(sim: &Mpu6886Sim) -> Mpu6886<SimInterface<'_>> {
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        mpu.init(&mut SimDelay).unwrap();
+        mpu
+    }
+
+    fn assert_close(a: Vector3<f32>, b: Vector3<f32>, eps: f32) {
+        assert!((a - b).amax() < eps, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn init() {
+        let sim = Mpu6886Sim::new();
+        sim.set_register(ACCEL_CONFIG::ADDR, 0x18);
+        let mut mpu = setup(&sim);
+        assert!(!mpu.get_sleep_enabled().unwrap());
+        assert_eq!(mpu.get_clock_source().unwrap(), CLKSEL::AUTOPLL1);
+        assert_eq!(mpu.get_accel_range().unwrap(), AccelRange::G2);
+        assert_eq!(mpu.get_gyro_range().unwrap(), GyroRange::D250);
+
+        sim.set_register(WHOAMI, 0x70);
+        assert!(matches!(mpu.init(&mut SimDelay), Err(Mpu6886Error::InvalidChipId(0x70))));
+    }
+
+    #[test]
+    fn constructors_with_sens() {
+        let sim = Mpu6886Sim::new();
+        sim.set_register(ACCEL_CONFIG::ADDR, 0x08);
+        sim.set_register(GYRO_CONFIG::ADDR, 0x18);
+        sim.push_sample(Vector3::new(0.0, 0.0, 3.0), Vector3::new(1.0, 0.0, 0.0), TEMP_OFFSET);
+
+        let mut mpu = Mpu6886::new_with_interface_and_sens(sim.interface(), AccelRange::G4, GyroRange::D2000);
+        assert_close(mpu.get_acc().unwrap(), Vector3::new(0.0, 0.0, 3.0), 1e-3);
+        assert_close(mpu.get_gyro().unwrap(), Vector3::new(1.0, 0.0, 0.0), 1e-3);
+        let _iface: SimInterface = mpu.release();
+    }
+
+    #[cfg(feature = "eh0")]
+    #[test]
+    fn eh0_constructors() {
+        let sim = Mpu6886Sim::new_with_addr(0x69);
+        sim.push_sample(Vector3::new(1.0, 0.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        let one_g = Vector3::new(1.0, 0.0, 0.0);
+
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh0>>::new(sim.i2c());
+        assert!(matches!(mpu.get_acc(), Err(Mpu6886Error::I2c(sim::SimError::Nack))));
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh0>>::new_with_sens(sim.i2c(), AccelRange::G2, GyroRange::D250);
+        assert!(mpu.get_acc().is_err());
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh0>>::new_with_addr(sim.i2c(), 0x69);
+        assert_eq!(mpu.get_acc().unwrap(), one_g);
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh0>>::new_with_addr_and_sens(sim.i2c(), 0x69, AccelRange::G4, GyroRange::D250);
+        assert_eq!(mpu.get_acc().unwrap(), one_g * 2.0);
+
+        let mut mpu = Mpu6886::new_spi(sim.spi(), sim.cs());
+        assert_eq!(mpu.get_acc().unwrap(), one_g);
+        let mut mpu = Mpu6886::new_spi_with_sens(sim.spi(), sim.cs(), AccelRange::G8, GyroRange::D250);
+        assert_eq!(mpu.get_acc().unwrap(), one_g * 4.0);
+    }
+
+    #[cfg(feature = "eh1")]
+    #[test]
+    fn eh1_constructors() {
+        let sim = Mpu6886Sim::new();
+        sim.push_sample(Vector3::new(1.0, 0.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        let one_g = Vector3::new(1.0, 0.0, 0.0);
+
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh1>>::new(sim.i2c());
+        assert_eq!(mpu.get_acc().unwrap(), one_g);
+        let mut mpu = Mpu6886::<I2cInterface<_, Eh1>>::new_with_addr(sim.i2c(), 0x69);
+        assert!(matches!(mpu.get_acc(), Err(Mpu6886Error::I2c(sim::SimError::Nack))));
+
+        let mut mpu = Mpu6886::new_spi_device(sim.spi());
+        assert_eq!(mpu.get_acc().unwrap(), one_g);
+        let mut mpu = Mpu6886::new_spi_device_with_sens(sim.spi(), AccelRange::G16, GyroRange::D250);
+        assert_eq!(mpu.get_acc().unwrap(), one_g * 8.0);
+    }
+
+    #[test]
+    fn power_management() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        mpu.set_clock_source(CLKSEL::OSCILL).unwrap();
+        assert_eq!(mpu.get_clock_source().unwrap(), CLKSEL::OSCILL);
+        mpu.set_sleep_enabled(true).unwrap();
+        assert!(mpu.get_sleep_enabled().unwrap());
+        mpu.set_temp_enabled(false).unwrap();
+        assert!(!mpu.get_temp_enabled().unwrap());
+        assert_eq!(sim.register(PWR_MGMT_1::ADDR), 0x48);
+        mpu.set_temp_enabled(true).unwrap();
+        assert!(mpu.get_temp_enabled().unwrap());
+
+        mpu.reset_device(&mut SimDelay).unwrap();
+        assert_eq!(sim.register(PWR_MGMT_1::ADDR), 0x41);
+        assert!(mpu.get_sleep_enabled().unwrap());
+    }
+
+    #[test]
+    fn accel_self_test_bits() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        mpu.set_accel_x_self_test(true).unwrap();
+        mpu.set_accel_z_self_test(true).unwrap();
+        assert!(mpu.get_accel_x_self_test().unwrap());
+        assert!(!mpu.get_accel_y_self_test().unwrap());
+        assert!(mpu.get_accel_z_self_test().unwrap());
+        mpu.set_accel_y_self_test(true).unwrap();
+        mpu.set_accel_x_self_test(false).unwrap();
+        assert!(!mpu.get_accel_x_self_test().unwrap());
+        assert!(mpu.get_accel_y_self_test().unwrap());
+        assert_eq!(sim.register(ACCEL_CONFIG::ADDR), 0x60);
+    }
+
+    #[test]
+    fn gyro_self_test_bits() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        mpu.set_gyro_range(GyroRange::D2000).unwrap();
+        mpu.set_gyro_x_self_test(true).unwrap();
+        mpu.set_gyro_z_self_test(true).unwrap();
+        assert!(mpu.get_gyro_x_self_test().unwrap());
+        assert!(!mpu.get_gyro_y_self_test().unwrap());
+        assert!(mpu.get_gyro_z_self_test().unwrap());
+        mpu.set_gyro_y_self_test(true).unwrap();
+        mpu.set_gyro_x_self_test(false).unwrap();
+        assert!(!mpu.get_gyro_x_self_test().unwrap());
+        assert!(mpu.get_gyro_y_self_test().unwrap());
+        assert_eq!(sim.register(GYRO_CONFIG::ADDR), 0x78);
+    }
+
+    #[test]
+    fn readings_in_all_ranges() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+        let acc = Vector3::new(0.5, -1.0, 1.5);
+        let gyro = Vector3::new(0.1, -2.0, 4.0);
+
+        for (arange, grange) in [(AccelRange::G2, GyroRange::D250), (AccelRange::G4, GyroRange::D500),
+                                 (AccelRange::G8, GyroRange::D1000), (AccelRange::G16, GyroRange::D2000)] {
+            mpu.set_accel_range(arange).unwrap();
+            mpu.set_gyro_range(grange).unwrap();
+            sim.push_sample(acc, gyro, 36.5);
+            assert_close(mpu.get_acc().unwrap(), acc, 1e-3);
+            assert_close(mpu.get_gyro().unwrap(), gyro, 1e-3);
+            assert!((mpu.get_temp().unwrap() - 36.5).abs() < 1e-2);
+
+            let all = mpu.read_all().unwrap();
+            assert_close(all.accel, acc, 1e-3);
+            assert_close(all.gyro, gyro, 1e-3);
+            assert!((all.temp - 36.5).abs() < 1e-2);
+        }
+
+        // 5 rad/s saturates at +-250 dps
+        mpu.set_gyro_range(GyroRange::D250).unwrap();
+        sim.set_gyro(Vector3::new(0.0, 0.0, 5.0));
+        sim.sample();
+        assert!((mpu.get_gyro().unwrap().z - 32767.0 / 131.0 * PI_180).abs() < 1e-4);
+    }
+
+    #[test]
+    fn raw_readings() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+        mpu.set_accel_range(AccelRange::G4).unwrap();
+        sim.push_sample(Vector3::new(0.5, -1.0, 1.5), Vector3::new(PI_180, 0.0, -PI_180), 35.0);
+
+        let raw = mpu.get_raw().unwrap();
+        assert_eq!(raw.accel, Vector3::new(4096, -8192, 12288));
+        assert_eq!(raw.gyro, Vector3::new(131, 0, -131));
+        assert_eq!(raw.temp, 3268);
+        assert_eq!(mpu.get_acc_raw().unwrap(), raw.accel);
+        assert_eq!(mpu.get_gyro_raw().unwrap(), raw.gyro);
+        assert_eq!(mpu.get_temp_raw().unwrap(), raw.temp);
+        assert_eq!(raw.accel_mg(AccelRange::G4), Vector3::new(500, -1000, 1500));
+        assert_eq!(raw.gyro_mdps(GyroRange::D250), Vector3::new(1000, 0, -1000));
+        assert_eq!(raw.temp_mdeg_c(), 35_000);
+
+        assert!(matches!(mpu.read_fifo_raw(), Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+        mpu.enable_fifo(true, true).unwrap();
+        assert!(matches!(mpu.read_fifo_raw(), Err(Mpu6886Error::SensorError(SensorError::NofFifoData))));
+        sim.sample();
+        assert_eq!(mpu.read_fifo_raw().unwrap(), raw);
+    }
+
+    #[test]
+    fn acc_angles() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        sim.push_sample(Vector3::new(0.0, 0.0, 1.0), Vector3::zeros(), TEMP_OFFSET);
+        assert_eq!(mpu.get_acc_angles().unwrap(), Vector2::new(0.0, 0.0));
+        sim.push_sample(Vector3::new(-1.0, 1.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        let angles = mpu.get_acc_angles().unwrap();
+        assert!((angles - Vector2::new(PI / 4.0, PI / 4.0)).amax() < 1e-3);
+    }
+
+    #[test]
+    fn filters_and_sample_rate() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        assert_eq!(mpu.get_gyro_bandwith().unwrap(), GyroBw::Hz250);
+        assert_eq!(mpu.get_sample_rate().unwrap(), 8000.0);
+        assert!(matches!(mpu.set_sample_rate_hz(100.0),
+                         Err(Mpu6886Error::SensorError(SensorError::SampleRateDivIneffective))));
+
+        mpu.set_gyro_bw(GyroBw::Hz41).unwrap();
+        mpu.set_accel_bw(AccelBw::Hz45).unwrap();
+        mpu.set_accel_averaging(AccelAveraging::X16).unwrap();
+        assert_eq!(mpu.get_gyro_bandwith().unwrap(), GyroBw::Hz41);
+        assert_eq!(mpu.get_accel_bandwith().unwrap(), AccelBw::Hz45);
+        assert_eq!(mpu.get_accel_averaging().unwrap(), AccelAveraging::X16);
+        // reserved bit of CONFIG kept
+        assert_eq!(sim.register(CONFIG::ADDR), 0x83);
+
+        assert_eq!(mpu.set_sample_rate_hz(200.0).unwrap(), 200.0);
+        assert_eq!(sim.register(SMPLRT_DIV), 4);
+        assert_eq!(mpu.get_sample_rate().unwrap(), 200.0);
+        assert_eq!(mpu.get_sample_rate_mhz().unwrap(), 200_000);
+
+        // accel filter bypassed, 4 kHz accel rate regardless of SMPLRT_DIV
+        mpu.set_accel_bw(AccelBw::Hz1046).unwrap();
+        assert_eq!(mpu.get_sample_rate().unwrap(), 4000.0);
+        assert!(matches!(mpu.set_sample_rate_hz(100.0),
+                         Err(Mpu6886Error::SensorError(SensorError::SampleRateDivIneffective))));
+        mpu.set_accel_bw(AccelBw::Hz45).unwrap();
+
+        mpu.set_gyro_bw(GyroBw::Hz8173).unwrap();
+        assert_eq!(mpu.get_sample_rate().unwrap(), 32000.0);
+    }
+
+    #[test]
+    fn low_power_accel() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+        mpu.set_gyro_bw(GyroBw::Hz41).unwrap();
+        mpu.set_sample_rate_hz(500.0).unwrap();
+        mpu.set_accel_bw(AccelBw::Hz1046).unwrap();
+        mpu.write_byte(LP_MODE_CFG::ADDR, 0x90).unwrap();
+        let full_power = [PWR_MGMT_1::ADDR, PWR_MGMT_2::ADDR, SMPLRT_DIV, ACCEL_CONFIG_2::ADDR, LP_MODE_CFG::ADDR]
+            .map(|reg| sim.register(reg));
+        assert!(!mpu.is_low_power());
+
+        assert_eq!(mpu.enter_low_power_accel(50.0, AccelAveraging::X16).unwrap(), 50.0);
+        assert!(mpu.is_low_power());
+        assert_eq!(sim.register(PWR_MGMT_1::ADDR), 0x21);
+        assert_eq!(sim.register(PWR_MGMT_2::ADDR), 0x07);
+        assert_eq!(sim.register(SMPLRT_DIV), 19);
+        assert_eq!(sim.register(LP_MODE_CFG::ADDR), 0x10);
+        assert_eq!(mpu.get_accel_averaging().unwrap(), AccelAveraging::X16);
+        assert_eq!(mpu.get_accel_bandwith().unwrap(), AccelBw::Hz218);
+
+        // second enter keeps the full power configuration
+        assert!((mpu.enter_low_power_accel(10.0, AccelAveraging::X4).unwrap() - 10.0).abs() < 1e-3);
+        // 1 kHz / 7
+        assert_eq!(mpu.enter_low_power_accel_mhz(140_000, AccelAveraging::X4).unwrap(), 142_857);
+        assert_eq!(sim.register(SMPLRT_DIV), 6);
+        mpu.exit_low_power().unwrap();
+        assert!(!mpu.is_low_power());
+        assert_eq!([PWR_MGMT_1::ADDR, PWR_MGMT_2::ADDR, SMPLRT_DIV, ACCEL_CONFIG_2::ADDR, LP_MODE_CFG::ADDR]
+                       .map(|reg| sim.register(reg)), full_power);
+        mpu.exit_low_power().unwrap();
+
+        assert!(matches!(mpu.enter_low_power_accel(2000.0, AccelAveraging::X4),
+                         Err(Mpu6886Error::SensorError(SensorError::InvalidSampleRate))));
+        assert!(!mpu.is_low_power());
+    }
+
+    #[test]
+    fn axis_standby() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+        sim.set_register(PWR_MGMT_2::ADDR, 0x80);
+        assert_eq!(mpu.get_enabled_axes().unwrap(), Axes::ALL);
+
+        mpu.set_enabled_axes(Axes::ACCEL_Z | Axes::GYRO_X | Axes::GYRO_Y).unwrap();
+        assert_eq!(sim.register(PWR_MGMT_2::ADDR), 0xb1);
+        assert_eq!(mpu.get_enabled_axes().unwrap(), Axes::ACCEL_Z | Axes::GYRO_X | Axes::GYRO_Y);
+
+        let (acc, gyro) = (Vector3::new(0.5, 0.5, 1.0), Vector3::new(1.0, -1.0, 0.5));
+        sim.push_sample(acc, gyro, TEMP_OFFSET);
+        let checked = mpu.get_acc_checked().unwrap();
+        assert_eq!((checked.x, checked.y), (None, None));
+        assert!((checked.z.unwrap() - 1.0).abs() < 1e-3);
+        let checked = mpu.get_gyro_checked().unwrap();
+        assert!((checked.x.unwrap() - 1.0).abs() < 1e-3 && (checked.y.unwrap() + 1.0).abs() < 1e-3);
+        assert_eq!(checked.z, None);
+
+        mpu.enable_fifo(true, true).unwrap();
+        sim.sample();
+        let data = mpu.read_fifo_checked().unwrap();
+        assert_eq!((data[0].x, data[0].y, data[1].z), (None, None, None));
+        assert!(data[0].z.is_some() && data[1].x.is_some() && data[2].x.is_some());
+
+        // low power keeps track of the gyro standby
+        mpu.enter_low_power_accel(100.0, AccelAveraging::X4).unwrap();
+        assert_eq!(mpu.get_gyro_checked().unwrap(), Vector3::new(None, None, None));
+        mpu.exit_low_power().unwrap();
+        assert!(mpu.get_gyro_checked().unwrap().x.is_some());
+
+        mpu.reset_device(&mut SimDelay).unwrap();
+        assert!(mpu.get_acc_checked().unwrap().x.is_some());
+        assert_eq!(mpu.get_enabled_axes().unwrap(), Axes::ALL);
+        // FIFO_EN is cleared by the reset as well
+        sim.sample();
+        assert!(matches!(mpu.read_fifo_checked(),
+                         Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+    }
+
+    #[test]
+    fn interrupts() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        sim.set_register(INT_PIN_CFG::ADDR, 0x04);
+        let pin = InterruptPinConfig::default().open_drain(true).latch(InterruptLatch::Latched);
+        mpu.set_interrupt_pin_config(pin).unwrap();
+        assert_eq!(sim.register(INT_PIN_CFG::ADDR), 0x64);
+        assert_eq!(mpu.get_interrupt_pin_config().unwrap(), pin);
+
+        mpu.set_interrupts_enabled(Interrupts::DATA_READY | Interrupts::FIFO_OVERFLOW).unwrap();
+        assert_eq!(sim.register(INT_ENABLE::ADDR), 0x11);
+        mpu.setup_motion_detection().unwrap();
+        assert_eq!(mpu.get_enabled_interrupts().unwrap(), Interrupts::WOM | Interrupts::DATA_READY | Interrupts::FIFO_OVERFLOW);
+        mpu.disable_wake_on_motion().unwrap();
+        assert_eq!(mpu.get_enabled_interrupts().unwrap(), Interrupts::DATA_READY | Interrupts::FIFO_OVERFLOW);
+
+        sim.trigger_wom(false, false, true);
+        sim.sample();
+        let status = mpu.get_interrupt_status().unwrap();
+        assert!(status.data_ready && status.wom.z && !status.wom.x && !status.fifo_overflow);
+        assert_eq!(mpu.get_interrupt_status().unwrap(), InterruptStatus::default());
+
+        // any read clears
+        mpu.set_interrupt_pin_config(pin.clear_on_any_read(true)).unwrap();
+        sim.sample();
+        mpu.get_acc().unwrap();
+        assert!(!mpu.get_interrupt_status().unwrap().data_ready);
+    }
+
+    #[test]
+    fn motion_detection() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        mpu.setup_motion_detection().unwrap();
+        assert_eq!(sim.register(ACCEL_WOM_X_THR), 40);
+        assert_eq!(sim.register(INT_PIN_CFG::ADDR), 0x20);
+        assert_eq!(sim.register(INT_ENABLE::ADDR), 0xe0);
+        assert_eq!(sim.register(ACCEL_INTEL_CTRL::ADDR), 0xc0);
+        assert!(!mpu.get_motion_detected().unwrap());
+
+        sim.trigger_wom(false, true, false);
+        assert!(mpu.get_motion_detected().unwrap());
+        // INT_STATUS is cleared on read
+        assert!(!mpu.get_motion_detected().unwrap());
+    }
+
+    #[test]
+    fn wake_on_motion() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        let config = WomConfig {
+            threshold_mg: [0, 102, 1020],
+            // writes the undocumented ACCEL_INTEL_MODE 0 of the MPU6886, the sim only stores it
+            mode: WomMode::Initial,
+            logic: WomLogic::And,
+            latch: InterruptLatch::Pulsed,
+        };
+        mpu.setup_wake_on_motion(&config).unwrap();
+        assert_eq!([sim.register(ACCEL_WOM_X_THR), sim.register(ACCEL_WOM_Y_THR), sim.register(ACCEL_WOM_Z_THR)],
+                   [0, 26, 255]);
+        assert_eq!(sim.register(INT_PIN_CFG::ADDR), 0x00);
+        assert_eq!(sim.register(ACCEL_INTEL_CTRL::ADDR), 0x81);
+
+        sim.trigger_wom(true, false, true);
+        sim.sample();
+        assert_eq!(mpu.get_wom_status().unwrap(), WomStatus { x: true, y: false, z: true });
+        assert_eq!(mpu.get_wom_status().unwrap(), WomStatus::default());
+
+        mpu.disable_wake_on_motion().unwrap();
+        assert_eq!(sim.register(INT_ENABLE::ADDR), 0x00);
+        assert_eq!(sim.register(ACCEL_INTEL_CTRL::ADDR), 0x01);
+
+        let config = WomConfig { threshold_mg: [0, 1021, 0], ..config };
+        assert!(matches!(mpu.setup_wake_on_motion(&config),
+                         Err(Mpu6886Error::SensorError(SensorError::InvalidWomThreshold))));
+    }
+
+    #[test]
+    fn fifo() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+        let (acc, gyro) = (Vector3::new(0.25, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.5));
+
+        // samples taken before enabling are dropped
+        sim.push_sample(-acc, gyro, 40.0);
+        mpu.enable_fifo(true, true).unwrap();
+        assert_eq!(sim.fifo_len(), 0);
+        sim.push_sample(acc, gyro, 40.0);
+        sim.push_sample(acc, gyro, 40.0);
+        assert_eq!(sim.fifo_len(), 28);
+
+        let data = mpu.read_fifo().unwrap();
+        assert_close(data[0], acc, 1e-3);
+        assert_close(data[1], gyro / PI_180, 1e-2);
+        assert!((data[2].x - 40.0).abs() < 1e-2);
+
+        let data = mpu.read_fifo_si().unwrap();
+        assert_close(data[0], acc * GRAVITY, 1e-2);
+        assert_close(data[1], gyro, 1e-3);
+
+        assert!(matches!(mpu.read_fifo(), Err(Mpu6886Error::SensorError(SensorError::NofFifoData))));
+
+        // accel only packets are not misread as accel, temp and gyro
+        mpu.enable_fifo(true, false).unwrap();
+        sim.push_sample(acc, gyro, 40.0);
+        sim.push_sample(acc, gyro, 40.0);
+        sim.push_sample(acc, gyro, 40.0);
+        assert!(matches!(mpu.read_fifo(), Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+        assert!(matches!(mpu.read_fifo_checked(), Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+        assert!(matches!(mpu.read_fifo_raw(), Err(Mpu6886Error::SensorError(SensorError::UnsupportedFifoLayout))));
+        assert_eq!(sim.fifo_len(), 18);
+    }
+
+    #[test]
+    fn register_access() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = setup(&sim);
+
+        mpu.write_byte(SMPLRT_DIV, 0x12).unwrap();
+        assert_eq!(mpu.read_byte(SMPLRT_DIV).unwrap(), 0x12);
+        mpu.write_bit(SMPLRT_DIV, 7, true).unwrap();
+        mpu.write_bits(SMPLRT_DIV, 3, 2, 0b11).unwrap();
+        assert_eq!(mpu.read_byte(SMPLRT_DIV).unwrap(), 0x9e);
+        assert_eq!(mpu.read_bits(SMPLRT_DIV, 7, 4).unwrap(), 0x9);
+
+        let mut buf = [0; 3];
+        mpu.read_bytes(SMPLRT_DIV, &mut buf).unwrap();
+        assert_eq!(buf, [0x9e, 0x80, 0x00]);
+
+        // read only
+        mpu.write_byte(WHOAMI, 0).unwrap();
+        assert_eq!(mpu.read_byte(WHOAMI).unwrap(), 0x19);
+    }
+}
@@ -0,0 +1,194 @@
+//! Raw sensor counts with integer unit conversions, and the scaled `ImuSample`
+//!
+//! The data registers from ACCEL_XOUT_H (0x3B) to GYRO_ZOUT_L (0x48) and an accel, temp, gyro
+//! FIFO packet share the same layout: accel x, y, z, temp, gyro x, y, z, each a big endian
+//! `i16`. [`RawSample`] holds one such burst. Its conversions use fixed point integer math
+//! for targets without FPU (milli-g, milli-degrees per second, milli-degrees celcius).
+//! [`ImuSample`] is the same burst scaled to floating point units.
+
+use nalgebra::Vector3;
+
+use crate::device::{AccelRange, GyroRange};
+use crate::fifo::FifoLayout;
+#[cfg(feature = "float")]
+use crate::device::{TEMP_OFFSET, TEMP_SENSITIVITY};
+#[cfg(feature = "float")]
+use crate::PI_180;
+
+/// Bytes of a data register burst from ACCEL_XOUT_H
+pub const RAW_SAMPLE_SIZE: usize = 14;
+
+/// Gyro sensitivity in LSB per 10 dps for `GyroRange` (131, 65.5, 32.8, 16.4 LSB/dps)
+const GYRO_LSB_10DPS: [i32; 4] = [1310, 655, 328, 164];
+
+/// Temp sensitivity in LSB per 10 degrees celcius (326.8 LSB/°C)
+const TEMP_LSB_10DEG: i32 = 3268;
+
+/// Temp offset in milli-degrees celcius
+const TEMP_OFFSET_MDEG: i32 = 25_000;
+
+/// Raw sensor counts of accel, temp and gyro, as read in one burst
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RawSample {
+    /// Accelerometer counts
+    pub accel: Vector3<i16>,
+    /// Temperature counts
+    pub temp: i16,
+    /// Gyro counts
+    pub gyro: Vector3<i16>,
+}
+
+impl RawSample {
+    /// Decodes a data register burst or an accel, temp, gyro FIFO packet
+    pub fn from_bytes(buf: &[u8; RAW_SAMPLE_SIZE]) -> Self {
+        Self::from_packet(FifoLayout::AccelTempGyro, buf)
+    }
+
+    /// Decodes a FIFO packet of the given layout, sensors not in the packet are 0
+    ///
+    /// packet must be `layout.size()` bytes.
+    pub(crate) fn from_packet(layout: FifoLayout, packet: &[u8]) -> Self {
+        // first word of temp and gyro
+        let t = if layout.has_accel() { 3 } else { 0 };
+        RawSample {
+            accel: if layout.has_accel() { vector(packet) } else { Vector3::zeros() },
+            temp: if layout.has_gyro() { word(packet, t) } else { 0 },
+            gyro: if layout.has_gyro() { vector(&packet[2 * t + 2..]) } else { Vector3::zeros() },
+        }
+    }
+
+    /// Acceleration in milli-g for the configured range
+    pub fn accel_mg(&self, range: AccelRange) -> Vector3<i32> {
+        self.accel.map(|raw| accel_mg(raw, range))
+    }
+
+    /// Angular rate in milli-degrees per second for the configured range
+    pub fn gyro_mdps(&self, range: GyroRange) -> Vector3<i32> {
+        self.gyro.map(|raw| gyro_mdps(raw, range))
+    }
+
+    /// Temperature in milli-degrees celcius
+    pub fn temp_mdeg_c(&self) -> i32 {
+        temp_mdeg_c(self.temp)
+    }
+}
+
+#[cfg(feature = "float")]
+/// Accel, temp and gyro of one sample instant, see `Mpu6886::read_all`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImuSample {
+    /// Acceleration in g
+    pub accel: Vector3<f32>,
+    /// Angular rate in rad/s
+    pub gyro: Vector3<f32>,
+    /// Temperature in degrees celcius
+    pub temp: f32,
+}
+
+#[cfg(feature = "float")]
+impl ImuSample {
+    /// Scales raw counts with the sensitivities in LSB/g and LSB/dps
+    pub(crate) fn from_raw(raw: &RawSample, acc_sensitivity: f32, gyro_sensitivity: f32) -> Self {
+        ImuSample {
+            accel: accel_g(raw.accel, acc_sensitivity),
+            gyro: gyro_rad_s(raw.gyro, gyro_sensitivity),
+            temp: temp_deg_c(raw.temp),
+        }
+    }
+}
+
+/// Big endian word i of buf
+fn word(buf: &[u8], i: usize) -> i16 {
+    i16::from_be_bytes([buf[2 * i], buf[2 * i + 1]])
+}
+
+/// x, y, z counts from the first 6 bytes of buf, as in the data registers and the FIFO
+pub(crate) fn vector(buf: &[u8]) -> Vector3<i16> {
+    Vector3::new(word(buf, 0), word(buf, 1), word(buf, 2))
+}
+
+/// Temperature counts from the 2 bytes of TEMP_OUT_H and TEMP_OUT_L
+pub(crate) fn temp(buf: &[u8; 2]) -> i16 {
+    i16::from_be_bytes(*buf)
+}
+
+#[cfg(feature = "float")]
+/// Accelerometer counts to g with the sensitivity in LSB/g
+pub(crate) fn accel_g(raw: Vector3<i16>, acc_sensitivity: f32) -> Vector3<f32> {
+    raw.map(f32::from) / acc_sensitivity
+}
+
+#[cfg(feature = "float")]
+/// Gyro counts to degrees per second with the sensitivity in LSB/dps
+pub(crate) fn gyro_dps(raw: Vector3<i16>, gyro_sensitivity: f32) -> Vector3<f32> {
+    raw.map(f32::from) / gyro_sensitivity
+}
+
+#[cfg(feature = "float")]
+/// Gyro counts to rad/s with the sensitivity in LSB/dps
+pub(crate) fn gyro_rad_s(raw: Vector3<i16>, gyro_sensitivity: f32) -> Vector3<f32> {
+    raw.map(f32::from) * (PI_180 / gyro_sensitivity)
+}
+
+#[cfg(feature = "float")]
+/// Temperature counts to degrees celcius
+pub(crate) fn temp_deg_c(raw: i16) -> f32 {
+    raw as f32 / TEMP_SENSITIVITY + TEMP_OFFSET
+}
+
+/// Accelerometer counts to milli-g, 16384 LSB/g at ±2 g halving with each range
+pub fn accel_mg(raw: i16, range: AccelRange) -> i32 {
+    div_round(raw as i32 * 1000, 16384 >> range as u8)
+}
+
+/// Gyro counts to milli-degrees per second
+pub fn gyro_mdps(raw: i16, range: GyroRange) -> i32 {
+    div_round(raw as i32 * 10_000, GYRO_LSB_10DPS[range as usize])
+}
+
+/// Temperature counts to milli-degrees celcius
+pub fn temp_mdeg_c(raw: i16) -> i32 {
+    div_round(raw as i32 * 10_000, TEMP_LSB_10DEG) + TEMP_OFFSET_MDEG
+}
+
+/// Division rounding to nearest, d > 0
+fn div_round(n: i32, d: i32) -> i32 {
+    if n < 0 { (n - d / 2) / d } else { (n + d / 2) / d }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_bytes() {
+        let buf = [0x40, 0x00, 0xc0, 0x00, 0x7f, 0xff, 0x00, 0x00, 0x00, 0x83, 0xff, 0x7d, 0x80, 0x00];
+        let raw = RawSample::from_bytes(&buf);
+        assert_eq!(raw.accel, Vector3::new(16384, -16384, i16::MAX));
+        assert_eq!(raw.temp, 0);
+        assert_eq!(raw.gyro, Vector3::new(131, -131, i16::MIN));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(accel_mg(16384, AccelRange::G2), 1000);
+        assert_eq!(accel_mg(-8192, AccelRange::G4), -1000);
+        assert_eq!(accel_mg(2048, AccelRange::G16), 1000);
+        assert_eq!(accel_mg(i16::MIN, AccelRange::G16), -16_000);
+        assert_eq!(accel_mg(10, AccelRange::G2), 1);
+
+        assert_eq!(gyro_mdps(131, GyroRange::D250), 1000);
+        assert_eq!(gyro_mdps(-131, GyroRange::D500), -2000);
+        assert_eq!(gyro_mdps(328, GyroRange::D1000), 10_000);
+        assert_eq!(gyro_mdps(i16::MAX, GyroRange::D2000), 1_997_988);
+
+        assert_eq!(temp_mdeg_c(0), 25_000);
+        assert_eq!(temp_mdeg_c(3268), 35_000);
+        assert_eq!(temp_mdeg_c(-327), 23_999);
+
+        let raw = RawSample { accel: Vector3::new(0, 4096, -4096), temp: 0, gyro: Vector3::new(0, 164, -164) };
+        assert_eq!(raw.accel_mg(AccelRange::G8), Vector3::new(0, 1000, -1000));
+        assert_eq!(raw.gyro_mdps(GyroRange::D2000), Vector3::new(0, 10_000, -10_000));
+        assert_eq!(raw.temp_mdeg_c(), 25_000);
+    }
+}
@@ -0,0 +1,282 @@
+//! Factory self-test
+//!
+//! [`Mpu6886::self_test`] runs the self-test of the datasheet: at ±250 dps, ±2 g, 1 kHz and with
+//! the 92 Hz gyro and 99 Hz accel low pass filters, gyro and accel are averaged with the
+//! self-test actuation off and on. The difference is the self-test response, it is compared with
+//! the response measured in production, stored as trim codes in SELF_TEST_X_GYRO etc.
+//! The previous configuration is restored afterwards.
+//!
+//! ```ignore
+//! let report = mpu.self_test(&mut delay, 200)?;
+//! if !report.passed() {
+//!     warn!("self-test failed, gyro {:?}, accel {:?}", report.gyro_ratio(), report.accel_ratio());
+//! }
+//! ```
+//!
+//! Pass criteria per axis:
+//! * gyro: response / factory response above [`GYRO_ST_MIN_RATIO`], without trim code at least
+//!   [`GYRO_ST_MIN_DPS`]
+//! * accel: response / factory response within [`ACCEL_ST_RATIO`], without trim code within
+//!   [`ACCEL_ST_LIMITS_MG`]
+//! * gyro offset at rest, with self-test off: at most [`GYRO_OFFSET_MAX_DPS`]
+
+use libm::{fabsf, powf};
+use nalgebra::Vector3;
+
+use crate::config::{AccelBw, GyroBw};
+use crate::device::*;
+use crate::error::*;
+use crate::interface::{Delay, Interface};
+use crate::Mpu6886;
+
+/// Minimum ratio of gyro self-test response to factory response
+pub const GYRO_ST_MIN_RATIO: f32 = 0.5;
+
+/// Range of the ratio of accel self-test response to factory response
+pub const ACCEL_ST_RATIO: (f32, f32) = (0.5, 1.5);
+
+/// Minimum gyro self-test response in dps of axes without trim code
+pub const GYRO_ST_MIN_DPS: f32 = 60.0;
+
+/// Range of the accel self-test response in mg of axes without trim code
+pub const ACCEL_ST_LIMITS_MG: (f32, f32) = (225.0, 675.0);
+
+/// Maximum gyro offset in dps at rest, with self-test off
+pub const GYRO_OFFSET_MAX_DPS: f32 = 20.0;
+
+/// Settling time in ms after changing the configuration or the self-test bits
+const SETTLE_MS: u8 = 20;
+
+/// Averaged gyro and accel counts
+type Averages = (Vector3<f32>, Vector3<f32>);
+
+/// Factory self-test response in LSB at ±250 dps or ±2 g of a trim code, 0 if not programmed
+pub fn factory_response(code: u8) -> f32 {
+    if code == 0 { 0.0 } else { 2620.0 * powf(1.01, code as f32 - 1.0) }
+}
+
+/// Result of `Mpu6886::self_test`, responses in LSB at ±250 dps (131 LSB/dps) and ±2 g
+/// (16384 LSB/g) like the factory trim
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelfTestReport {
+    /// Gyro self-test response
+    pub gyro_response: Vector3<f32>,
+    /// Gyro factory response from SELF_TEST_X/Y/Z_GYRO, 0 if not programmed
+    pub gyro_factory: Vector3<f32>,
+    /// Gyro axes within the limits
+    pub gyro_passed: Vector3<bool>,
+    /// Gyro offset at rest, with self-test off
+    pub gyro_offset: Vector3<f32>,
+    /// Gyro axes with an offset within `GYRO_OFFSET_MAX_DPS`
+    pub gyro_offset_passed: Vector3<bool>,
+    /// Accel self-test response
+    pub accel_response: Vector3<f32>,
+    /// Accel factory response from SELF_TEST_X/Y/Z_ACCEL, 0 if not programmed
+    pub accel_factory: Vector3<f32>,
+    /// Accel axes within the limits
+    pub accel_passed: Vector3<bool>,
+}
+
+impl SelfTestReport {
+    /// Evaluates the measured responses against the factory trim codes and the gyro offset
+    /// against `GYRO_OFFSET_MAX_DPS`
+    pub fn new(gyro_response: Vector3<f32>, accel_response: Vector3<f32>, gyro_offset: Vector3<f32>,
+               gyro_codes: [u8; 3], accel_codes: [u8; 3]) -> Self {
+        let gyro_factory = Vector3::from(gyro_codes).map(factory_response);
+        let accel_factory = Vector3::from(accel_codes).map(factory_response);
+        let gyro_passed = gyro_response.zip_map(&gyro_factory, |response, factory| {
+            if factory == 0.0 {
+                fabsf(response) >= GYRO_ST_MIN_DPS * GYRO_SENS.0
+            } else {
+                response / factory > GYRO_ST_MIN_RATIO
+            }
+        });
+        let accel_passed = accel_response.zip_map(&accel_factory, |response, factory| {
+            if factory == 0.0 {
+                let mg = fabsf(response) * 1000.0 / ACCEL_SENS.0;
+                (ACCEL_ST_LIMITS_MG.0..=ACCEL_ST_LIMITS_MG.1).contains(&mg)
+            } else {
+                let ratio = response / factory;
+                ratio > ACCEL_ST_RATIO.0 && ratio < ACCEL_ST_RATIO.1
+            }
+        });
+        let gyro_offset_passed = gyro_offset.map(|offset| fabsf(offset) <= GYRO_OFFSET_MAX_DPS * GYRO_SENS.0);
+        SelfTestReport {
+            gyro_response, gyro_factory, gyro_passed, gyro_offset, gyro_offset_passed,
+            accel_response, accel_factory, accel_passed,
+        }
+    }
+
+    /// whether all axes passed
+    pub fn passed(&self) -> bool {
+        self.gyro_passed.iter()
+            .chain(self.gyro_offset_passed.iter())
+            .chain(self.accel_passed.iter())
+            .all(|passed| *passed)
+    }
+
+    /// Gyro response relative to the factory response, infinite for axes without trim code
+    pub fn gyro_ratio(&self) -> Vector3<f32> {
+        self.gyro_response.component_div(&self.gyro_factory)
+    }
+
+    /// Accel response relative to the factory response, infinite for axes without trim code
+    pub fn accel_ratio(&self) -> Vector3<f32> {
+        self.accel_response.component_div(&self.accel_factory)
+    }
+}
+
+impl<I, E> Mpu6886<I>
+where
+    I: Interface<Error = E>,
+{
+    /// Factory self-test of gyro and accel, see the [module documentation](crate::selftest)
+    ///
+    /// Averages samples readings with self-test off and on, one per ms. The sensor must be at
+    /// rest, awake and have all axes enabled. Sample rate, ranges, filters and self-test bits
+    /// (SMPLRT_DIV to ACCEL_CONFIG_2) are restored afterwards, also if the test failed with a
+    /// bus error. FIFO data written during the test is in the self-test configuration.
+    pub fn self_test<D: Delay<I::Hal>>(&mut self, delay: &mut D, samples: u16) -> Result<SelfTestReport, Mpu6886Error<E>> {
+        if samples < 2 {
+            return Err(SensorError::NotEnoughSamples.into());
+        }
+        let mut config = [0; 5];
+        self.read_bytes(SMPLRT_DIV, &mut config)?;
+
+        let response = self.self_test_response(delay, samples);
+        let restored = (SMPLRT_DIV..).zip(config).try_for_each(|(reg, byte)| self.write_byte(reg, byte));
+        let ((gyro_off, accel_off), (gyro_on, accel_on)) = response?;
+        restored?;
+
+        let (mut gyro_codes, mut accel_codes) = ([0; 3], [0; 3]);
+        self.read_bytes(SELF_TEST_X_GYRO, &mut gyro_codes)?;
+        self.read_bytes(SELF_TEST_X_ACCEL, &mut accel_codes)?;
+        Ok(SelfTestReport::new(gyro_on - gyro_off, accel_on - accel_off, gyro_off, gyro_codes, accel_codes))
+    }
+
+    /// Configures the self-test conditions and averages gyro and accel counts with self-test
+    /// off and on
+    fn self_test_response<D: Delay<I::Hal>>(&mut self, delay: &mut D, samples: u16)
+                                            -> Result<(Averages, Averages), Mpu6886Error<E>> {
+        // ±250 dps, ±2 g, self-test off, 1 kHz
+        self.write_byte(GYRO_CONFIG::ADDR, 0)?;
+        self.write_byte(ACCEL_CONFIG::ADDR, 0)?;
+        self.set_gyro_bw(GyroBw::Hz92)?;
+        self.set_accel_bw(AccelBw::Hz99)?;
+        self.write_byte(SMPLRT_DIV, 0)?;
+        delay.delay_ms(SETTLE_MS);
+        let off = self.average_raw(delay, samples)?;
+
+        self.write_bits(GYRO_CONFIG::ADDR, GYRO_CONFIG::XG_ST, 3, 0b111)?;
+        self.write_bits(ACCEL_CONFIG::ADDR, ACCEL_CONFIG::XA_ST, 3, 0b111)?;
+        delay.delay_ms(SETTLE_MS);
+        let on = self.average_raw(delay, samples)?;
+
+        Ok((off, on))
+    }
+
+    /// Averages samples gyro and accel counts, one per ms
+    fn average_raw<D: Delay<I::Hal>>(&mut self, delay: &mut D, samples: u16) -> Result<Averages, Mpu6886Error<E>> {
+        let (mut gyro, mut accel) = (Vector3::zeros(), Vector3::zeros());
+        for _ in 0..samples {
+            delay.delay_ms(1);
+            let raw = self.get_raw()?;
+            gyro += raw.gyro.map(f32::from);
+            accel += raw.accel.map(f32::from);
+        }
+        Ok((gyro / samples as f32, accel / samples as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Mpu6886Sim;
+
+    #[test]
+    fn factory_trim() {
+        assert_eq!(factory_response(0), 0.0);
+        assert_eq!(factory_response(1), 2620.0);
+        assert!((factory_response(101) - 2620.0 * 2.7048).abs() < 1.0);
+
+        let factory = factory_response(100);
+        let report = SelfTestReport::new(Vector3::new(factory, 0.51 * factory, 0.49 * factory),
+                                         Vector3::new(factory, 1.49 * factory, 1.51 * factory),
+                                         Vector3::zeros(), [100; 3], [100; 3]);
+        assert_eq!(report.gyro_passed, Vector3::new(true, true, false));
+        assert_eq!(report.accel_passed, Vector3::new(true, true, false));
+        assert!(!report.passed());
+        assert!((report.accel_ratio() - Vector3::new(1.0, 1.49, 1.51)).amax() < 1e-6);
+
+        // absolute limits without trim code
+        let report = SelfTestReport::new(Vector3::new(-60.0, 59.0, 0.0) * 131.0,
+                                         Vector3::new(0.225, -0.5, 0.7) * 16384.0,
+                                         Vector3::zeros(), [0; 3], [0; 3]);
+        assert_eq!(report.gyro_passed, Vector3::new(true, false, false));
+        assert_eq!(report.accel_passed, Vector3::new(true, true, false));
+        assert!(report.gyro_ratio().x.is_infinite());
+
+        // gyro offset at rest
+        let report = SelfTestReport::new(Vector3::repeat(factory), Vector3::repeat(factory),
+                                         Vector3::new(-20.0, 20.5, 0.0) * 131.0, [100; 3], [100; 3]);
+        assert_eq!(report.gyro_offset_passed, Vector3::new(true, false, true));
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn self_test() {
+        let sim = Mpu6886Sim::new();
+        let mut mpu = Mpu6886::new_with_interface(sim.interface());
+        mpu.set_gyro_range(GyroRange::D2000).unwrap();
+        mpu.set_accel_range(AccelRange::G8).unwrap();
+        mpu.set_gyro_bw(GyroBw::Hz41).unwrap();
+        mpu.set_accel_bw(AccelBw::Hz21).unwrap();
+        mpu.set_sample_rate_hz(100.0).unwrap();
+        mpu.set_accel_z_self_test(true).unwrap();
+        let config: [u8; 5] = core::array::from_fn(|i| sim.register(SMPLRT_DIV + i as u8));
+
+        let acc = Vector3::new(0.0, 0.1, 1.0);
+        let gyro = Vector3::new(0.1, -0.2, 0.05);
+        let mut delay = sim.sampling_delay(|sim, _| {
+            sim.set_accel(acc);
+            sim.set_gyro(gyro);
+        });
+        let report = mpu.self_test(&mut delay, 50).unwrap();
+        assert!(report.passed(), "{:?}", report);
+        assert!((report.gyro_ratio().add_scalar(-1.0)).amax() < 1e-3);
+        assert!((report.accel_ratio().add_scalar(-1.0)).amax() < 1e-3);
+
+        // configuration and sensitivities restored
+        let restored: [u8; 5] = core::array::from_fn(|i| sim.register(SMPLRT_DIV + i as u8));
+        assert_eq!(restored, config);
+        mpu.set_accel_z_self_test(false).unwrap();
+        sim.sample();
+        assert!((mpu.get_acc().unwrap() - acc).amax() < 1e-3);
+        assert!((mpu.get_gyro().unwrap() - gyro).amax() < 1e-3);
+
+        // weak gyro y, strong accel x
+        let (gyro_st, accel_st) = sim.self_test_response();
+        sim.set_self_test_response(gyro_st.component_mul(&Vector3::new(1.0, 0.4, 1.0)),
+                                   accel_st.component_mul(&Vector3::new(1.6, 1.0, 1.0)));
+        let report = mpu.self_test(&mut delay, 50).unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.gyro_passed, Vector3::new(true, false, true));
+        assert_eq!(report.accel_passed, Vector3::new(false, true, true));
+
+        // gyro z without trim code, checked against GYRO_ST_MIN_DPS
+        sim.set_register(SELF_TEST_Z_GYRO, 0);
+        sim.set_self_test_response(Vector3::repeat(70.0), accel_st);
+        let report = mpu.self_test(&mut delay, 50).unwrap();
+        assert_eq!(report.gyro_factory.z, 0.0);
+        assert!((report.gyro_response.z / 131.0 - 70.0).abs() < 0.1);
+        assert!(report.passed());
+
+        // gyro x with a zero-rate output stuck at 25 dps
+        let mut delay = sim.sampling_delay(|sim, _| sim.set_gyro(Vector3::new(25.0 * crate::PI_180, 0.0, 0.0)));
+        let report = mpu.self_test(&mut delay, 50).unwrap();
+        assert!((report.gyro_offset.x / 131.0 - 25.0).abs() < 0.1);
+        assert_eq!(report.gyro_offset_passed, Vector3::new(false, true, true));
+        assert!(report.gyro_passed.iter().all(|passed| *passed));
+        assert!(!report.passed());
+    }
+}
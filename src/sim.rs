@@ -0,0 +1,843 @@
+//! In-memory model of the mpu6886 register map, to test applications without a board
+//!
+//! Enabled by the feature `sim`. [`Mpu6886Sim`] holds the register file, the driver talks to it
+//! through one of its bus handles, while the test keeps driving the simulated sensor:
+//!
+//! ```
+//! use mpu6886::Mpu6886;
+//! use mpu6886::sim::{Mpu6886Sim, SimDelay};
+//! use nalgebra::Vector3;
+//!
+//! let sim = Mpu6886Sim::new();
+//! let mut mpu = Mpu6886::new_with_interface(sim.interface());
+//! mpu.init(&mut SimDelay).unwrap();
+//!
+//! sim.push_sample(Vector3::new(0.0, 0.0, 1.0), Vector3::zeros(), 25.0);
+//! assert_eq!(mpu.get_acc().unwrap(), Vector3::new(0.0, 0.0, 1.0));
+//! ```
+//!
+//! What is modelled:
+//! * WHO_AM_I (0x19) and reset values, restored by `DEVICE_RESET`
+//! * read-only registers (INT_STATUS, sensor data, FIFO count, WHO_AM_I), writes are ignored
+//! * clear-on-read INT_STATUS (any read clears it, if `INT_RD_CLEAR` is set)
+//! * auto increment burst reads and writes, FIFO_R_W is not incremented
+//! * 1 kB FIFO filled with the enabled sensors on every sample, overwrite or stop-when-full
+//!   (filled to the last byte, the rest of the packet is dropped) with overflow status,
+//!   watermark status, `FIFO_RST` of USER_CTRL, packets written partially with
+//!   [`Mpu6886Sim::sample_partial`]
+//! * gyro user offsets (XG_OFFS_USR etc.) and accel offsets (XA_OFFSET etc.) added to the
+//!   outputs, the accel offsets reset to 0 instead of factory trim values
+//! * self-test bits of GYRO_CONFIG and ACCEL_CONFIG adding the self-test response, by default
+//!   the factory response of the trim codes in SELF_TEST_X_GYRO etc., which survive a reset
+//!
+//! Interrupt status bits are set whenever their event happens, independent of INT_ENABLE.
+//! Samples are only produced on [`Mpu6886Sim::sample`], there is no simulated time.
+//! [`Mpu6886Sim::sampling_delay`] takes a sample on every delay, for driver routines that
+//! wait for new data.
+
+use core::cell::RefCell;
+use libm::roundf;
+use nalgebra::Vector3;
+
+use crate::device::*;
+use crate::interface::{Delay, Interface};
+use crate::PI_180;
+
+/// Size of the FIFO in bytes
+const FIFO_SIZE: usize = crate::device::FIFO_SIZE as usize;
+
+/// Reset value of PWR_MGMT_1: sleep, auto select clock
+const PWR_MGMT_1_RESET: u8 = 0x41;
+/// Reset value of CONFIG: reserved bit 7 set
+const CONFIG_RESET: u8 = 0x80;
+/// Self-test trim code registers, kept on reset
+const SELF_TEST_REGS: [u8; 6] = [SELF_TEST_X_GYRO, SELF_TEST_Y_GYRO, SELF_TEST_Z_GYRO,
+                                 SELF_TEST_X_ACCEL, SELF_TEST_Y_ACCEL, SELF_TEST_Z_ACCEL];
+/// Self-test trim codes of the simulated part, gyro x, y, z, accel x, y, z
+const SELF_TEST_CODES: [u8; 6] = [118, 122, 126, 108, 112, 116];
+
+/// Errors of the simulated buses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// I2C transfer to another slave address than the one of the simulator
+    Nack,
+}
+
+/// Registers, FIFO and the values the next sample is taken from
+struct State {
+    regs: [u8; 128],
+    fifo: [u8; FIFO_SIZE],
+    fifo_head: usize,
+    fifo_len: usize,
+    /// rest of the last FIFO packet, not written yet, see `Mpu6886Sim::sample_partial`
+    fifo_unwritten: ([u8; 14], usize),
+    slave_addr: u8,
+    accel: Vector3<f32>,
+    gyro: Vector3<f32>,
+    temp: f32,
+    /// self-test response of gyro in dps and accel in g
+    self_test: (Vector3<f32>, Vector3<f32>),
+    /// register pointer and read flag of an ongoing spi transaction
+    #[cfg(any(feature = "eh0", feature = "eh1"))]
+    spi: Option<(u8, bool)>,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut state = State {
+            regs: [0; 128],
+            fifo: [0; FIFO_SIZE],
+            fifo_head: 0,
+            fifo_len: 0,
+            fifo_unwritten: ([0; 14], 0),
+            slave_addr: DEFAULT_SLAVE_ADDR,
+            accel: Vector3::zeros(),
+            gyro: Vector3::zeros(),
+            temp: TEMP_OFFSET,
+            self_test: (Vector3::zeros(), Vector3::zeros()),
+            #[cfg(any(feature = "eh0", feature = "eh1"))]
+            spi: None,
+        };
+        let factory = |i: usize| crate::selftest::factory_response(SELF_TEST_CODES[i]);
+        state.self_test = (Vector3::from_fn(|i, _| factory(i) / GYRO_SENS.0),
+                           Vector3::from_fn(|i, _| factory(i + 3) / ACCEL_SENS.0));
+        for (reg, code) in SELF_TEST_REGS.iter().zip(SELF_TEST_CODES) {
+            state.regs[*reg as usize] = code;
+        }
+        state.reset();
+        state
+    }
+
+    /// Restores reset values of all registers and clears the FIFO
+    fn reset(&mut self) {
+        let regs = self.regs;
+        self.regs = [0; 128];
+        for reg in SELF_TEST_REGS {
+            self.regs[reg as usize] = regs[reg as usize];
+        }
+        self.regs[PWR_MGMT_1::ADDR as usize] = PWR_MGMT_1_RESET;
+        self.regs[CONFIG::ADDR as usize] = CONFIG_RESET;
+        self.regs[WHOAMI as usize] = CHIP_ID;
+        self.fifo_reset();
+    }
+
+    fn fifo_reset(&mut self) {
+        self.fifo_head = 0;
+        self.fifo_len = 0;
+        self.fifo_unwritten.1 = 0;
+    }
+
+    fn read_only(reg: u8) -> bool {
+        matches!(reg, FIFO_WM_INT_STATUS::ADDR..=0x48 | FIFO_COUNTH | FIFO_COUNTL | WHOAMI)
+    }
+
+    fn reg_bit(&self, reg: u8, bit: u8) -> bool {
+        self.regs[reg as usize] & (1 << bit) != 0
+    }
+
+    fn set_int_status(&mut self, bit: u8) {
+        self.regs[INT_STATUS::ADDR as usize] |= 1 << bit;
+    }
+
+    /// Register read as seen from the bus, with read side effects
+    fn read(&mut self, reg: u8) -> u8 {
+        let value = match reg {
+            FIFO_R_W => self.fifo_pop().unwrap_or(0xff),
+            FIFO_COUNTH => (self.fifo_len >> 8) as u8 & 0x1f,
+            FIFO_COUNTL => self.fifo_len as u8,
+            _ => self.regs[reg as usize],
+        };
+        if reg == INT_STATUS::ADDR || self.reg_bit(INT_PIN_CFG::ADDR, INT_PIN_CFG::INT_RD_CLEAR) {
+            self.regs[INT_STATUS::ADDR as usize] = 0;
+        }
+        if reg == FIFO_WM_INT_STATUS::ADDR {
+            self.regs[reg as usize] = 0;
+        }
+        value
+    }
+
+    /// Register write as seen from the bus, with write side effects
+    fn write(&mut self, reg: u8, value: u8) {
+        if Self::read_only(reg) {
+            return;
+        }
+        match reg {
+            PWR_MGMT_1::ADDR if value & (1 << PWR_MGMT_1::DEVICE_RESET) != 0 => self.reset(),
+            USER_CTRL => {
+                // reset bits clear themselves
+                if value & (1 << 2) != 0 {
+                    self.fifo_reset();
+                }
+                self.regs[USER_CTRL as usize] = value & !0b0000_0101;
+            }
+            _ => self.regs[reg as usize] = value,
+        }
+    }
+
+    /// Next register address of a burst access
+    fn next(reg: u8) -> u8 {
+        if reg == FIFO_R_W { reg } else { (reg + 1) & 0x7f }
+    }
+
+    fn read_burst(&mut self, reg: u8, buf: &mut [u8]) {
+        let mut reg = reg & 0x7f;
+        for byte in buf.iter_mut() {
+            *byte = self.read(reg);
+            reg = Self::next(reg);
+        }
+    }
+
+    #[cfg(any(feature = "eh0", feature = "eh1"))]
+    fn write_burst(&mut self, reg: u8, data: &[u8]) {
+        let mut reg = reg & 0x7f;
+        for byte in data {
+            self.write(reg, *byte);
+            reg = Self::next(reg);
+        }
+    }
+
+    /// One byte of a spi transaction, first byte is the address with read flag
+    #[cfg(any(feature = "eh0", feature = "eh1"))]
+    fn spi_exchange(&mut self, out: u8) -> u8 {
+        match self.spi {
+            None => {
+                self.spi = Some((out & 0x7f, out & 0x80 != 0));
+                0
+            }
+            Some((reg, read)) => {
+                self.spi = Some((Self::next(reg), read));
+                if read {
+                    self.read(reg)
+                } else {
+                    self.write(reg, out);
+                    0
+                }
+            }
+        }
+    }
+
+    fn fifo_pop(&mut self) -> Option<u8> {
+        if self.fifo_len == 0 {
+            return None;
+        }
+        let byte = self.fifo[self.fifo_head];
+        self.fifo_head = (self.fifo_head + 1) % FIFO_SIZE;
+        self.fifo_len -= 1;
+        Some(byte)
+    }
+
+    fn fifo_push(&mut self, mut packet: &[u8]) {
+        if self.fifo_len + packet.len() > FIFO_SIZE {
+            self.set_int_status(INT_STATUS::FIFO_OFLOW_INT);
+            if self.reg_bit(CONFIG::ADDR, CONFIG::FIFO_MODE) {
+                // written until the FIFO is full
+                packet = &packet[..FIFO_SIZE - self.fifo_len];
+            } else {
+                // overwrite oldest data
+                let drop = self.fifo_len + packet.len() - FIFO_SIZE;
+                self.fifo_head = (self.fifo_head + drop) % FIFO_SIZE;
+                self.fifo_len -= drop;
+            }
+        }
+        for byte in packet {
+            self.fifo[(self.fifo_head + self.fifo_len) % FIFO_SIZE] = *byte;
+            self.fifo_len += 1;
+        }
+        let watermark = ((self.regs[FIFO_WM_TH1 as usize] as usize & 0b11) << 8) | self.regs[FIFO_WM_TH2 as usize] as usize;
+        if watermark != 0 && self.fifo_len >= watermark {
+            self.regs[FIFO_WM_INT_STATUS::ADDR as usize] |= 1 << FIFO_WM_INT_STATUS::FIFO_WM_INT;
+        }
+    }
+
+    fn accel_sensitivity(&self) -> f32 {
+        AccelRange::from(crate::bits::get_bits(self.regs[ACCEL_CONFIG::ADDR as usize],
+                                               ACCEL_CONFIG::FS_SEL.bit, ACCEL_CONFIG::FS_SEL.length)).sensitivity()
+    }
+
+    /// Accel offsets in output counts of the current range
+    fn accel_offset(&self) -> Vector3<f32> {
+        let word = |reg: u8| (i16::from_be_bytes([self.regs[reg as usize], self.regs[reg as usize + 1]]) >> 1) as f32;
+        let offset = Vector3::new(word(XA_OFFSET_H), word(YA_OFFSET_H), word(ZA_OFFSET_H));
+        offset * ACCEL_OFFSET_LSB_MG / 1000.0 * self.accel_sensitivity()
+    }
+
+    fn gyro_range(&self) -> GyroRange {
+        GyroRange::from(crate::bits::get_bits(self.regs[GYRO_CONFIG::ADDR as usize],
+                                              GYRO_CONFIG::FS_SEL.bit, GYRO_CONFIG::FS_SEL.length))
+    }
+
+    fn gyro_sensitivity(&self) -> f32 {
+        self.gyro_range().sensitivity()
+    }
+
+    /// Gyro user offsets in output counts of the current range
+    fn gyro_offset(&self) -> Vector3<f32> {
+        let word = |reg: u8| i16::from_be_bytes([self.regs[reg as usize], self.regs[reg as usize + 1]]) as f32;
+        let scale = 4.0 / (1 << self.gyro_range() as u8) as f32;
+        Vector3::new(word(XG_OFFS_USRH), word(YG_OFFS_USRH), word(ZG_OFFS_USRH)) * scale
+    }
+
+    /// 1 for axes with the self-test bit set in GYRO_CONFIG or ACCEL_CONFIG, 0 otherwise
+    fn self_test_axes(&self, reg: u8) -> Vector3<f32> {
+        Vector3::new(7, 6, 5).map(|bit| if self.reg_bit(reg, bit) { 1.0 } else { 0.0 })
+    }
+
+    /// Latches the current values into the data registers and the first written bytes of
+    /// the packet into the FIFO
+    fn sample(&mut self, written: usize) {
+        self.finish_fifo_packet();
+        let accel_st = self.self_test_axes(ACCEL_CONFIG::ADDR).component_mul(&self.self_test.1);
+        let gyro_st = self.self_test_axes(GYRO_CONFIG::ADDR).component_mul(&self.self_test.0);
+        let accel = (self.accel + accel_st) * self.accel_sensitivity() + self.accel_offset();
+        let gyro = (self.gyro / PI_180 + gyro_st) * self.gyro_sensitivity() + self.gyro_offset();
+        let temp = (self.temp - TEMP_OFFSET) * TEMP_SENSITIVITY;
+
+        let mut data = [0u8; 14];
+        for (i, value) in [accel.x, accel.y, accel.z, temp, gyro.x, gyro.y, gyro.z].iter().enumerate() {
+            // float to int casts saturate, like the sensor does at full scale
+            data[2 * i..2 * i + 2].copy_from_slice(&(roundf(*value) as i16).to_be_bytes());
+        }
+        self.regs[ACC_REGX_H as usize..ACC_REGX_H as usize + 14].copy_from_slice(&data);
+        self.set_int_status(INT_STATUS::DATA_RDY_INT);
+
+        if self.reg_bit(USER_CTRL, 6) {
+            let mut packet = [0u8; 14];
+            let mut len = 0;
+            if self.reg_bit(FIFO_EN, 3) {
+                packet[..6].copy_from_slice(&data[..6]);
+                len = 6;
+            }
+            if self.reg_bit(FIFO_EN, 4) {
+                packet[len..len + 8].copy_from_slice(&data[6..]);
+                len += 8;
+            }
+            let written = written.min(len);
+            self.fifo_push(&packet[..written]);
+            self.fifo_unwritten.0[..len - written].copy_from_slice(&packet[written..len]);
+            self.fifo_unwritten.1 = len - written;
+        }
+    }
+
+    /// Writes the rest of a partially written FIFO packet
+    fn finish_fifo_packet(&mut self) {
+        let (rest, len) = self.fifo_unwritten;
+        self.fifo_unwritten.1 = 0;
+        self.fifo_push(&rest[..len]);
+    }
+}
+
+/// Simulated mpu6886, see the [module documentation](self)
+pub struct Mpu6886Sim {
+    state: RefCell<State>,
+}
+
+impl Default for Mpu6886Sim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mpu6886Sim {
+    /// Simulator with reset register values at the default slave address
+    pub fn new() -> Self {
+        Mpu6886Sim { state: RefCell::new(State::new()) }
+    }
+
+    /// Same as `new`, answering to another slave address on i2c
+    pub fn new_with_addr(slave_addr: u8) -> Self {
+        let sim = Self::new();
+        sim.state.borrow_mut().slave_addr = slave_addr;
+        sim
+    }
+
+    /// Driver interface to the simulator, use with `Mpu6886::new_with_interface`
+    pub fn interface(&self) -> SimInterface<'_> {
+        SimInterface { sim: self }
+    }
+
+    /// i2c bus to the simulator, implements the enabled `embedded-hal` i2c traits
+    #[cfg(any(feature = "eh0", feature = "eh1"))]
+    pub fn i2c(&self) -> SimI2c<'_> {
+        SimI2c { sim: self }
+    }
+
+    /// spi bus to the simulator, a `embedded-hal` 1.0 `SpiDevice`, or with [`cs`](Self::cs)
+    /// a `embedded-hal` 0.2 bus
+    #[cfg(any(feature = "eh0", feature = "eh1"))]
+    pub fn spi(&self) -> SimSpi<'_> {
+        SimSpi { sim: self }
+    }
+
+    /// chip select pin for the `embedded-hal` 0.2 spi bus
+    #[cfg(feature = "eh0")]
+    pub fn cs(&self) -> SimCs<'_> {
+        SimCs { sim: self }
+    }
+
+    /// Sets the acceleration in g, applied with the next sample
+    pub fn set_accel(&self, accel: Vector3<f32>) {
+        self.state.borrow_mut().accel = accel;
+    }
+
+    /// Sets the angular rate in rad/s, applied with the next sample
+    pub fn set_gyro(&self, gyro: Vector3<f32>) {
+        self.state.borrow_mut().gyro = gyro;
+    }
+
+    /// Sets the die temperature in degrees celcius, applied with the next sample
+    pub fn set_temp(&self, temp: f32) {
+        self.state.borrow_mut().temp = temp;
+    }
+
+    /// Sets the self-test response of gyro in dps and accel in g, added to the axes with
+    /// self-test enabled
+    pub fn set_self_test_response(&self, gyro: Vector3<f32>, accel: Vector3<f32>) {
+        self.state.borrow_mut().self_test = (gyro, accel);
+    }
+
+    /// Self-test response of gyro in dps and accel in g
+    pub fn self_test_response(&self) -> (Vector3<f32>, Vector3<f32>) {
+        self.state.borrow().self_test
+    }
+
+    /// Takes a sample: updates the data registers with the current full scale ranges,
+    /// pushes the enabled sensors into the FIFO and sets DATA_RDY_INT
+    pub fn sample(&self) {
+        self.state.borrow_mut().sample(usize::MAX);
+    }
+
+    /// Same as `sample`, but only the first bytes of the FIFO packet are written, like a FIFO
+    /// read while the sensor writes. The rest follows with `finish_sample` or the next sample.
+    pub fn sample_partial(&self, bytes: usize) {
+        self.state.borrow_mut().sample(bytes);
+    }
+
+    /// Writes the rest of the FIFO packet of `sample_partial`
+    pub fn finish_sample(&self) {
+        self.state.borrow_mut().finish_fifo_packet();
+    }
+
+    /// Combination of `set_accel`, `set_gyro`, `set_temp` and `sample`
+    pub fn push_sample(&self, accel: Vector3<f32>, gyro: Vector3<f32>, temp: f32) {
+        self.set_accel(accel);
+        self.set_gyro(gyro);
+        self.set_temp(temp);
+        self.sample();
+    }
+
+    /// Delay that takes a sample on every call, after stimulus(sim, n) set up sample n
+    ///
+    /// ```
+    /// # use mpu6886::sim::Mpu6886Sim;
+    /// # use nalgebra::Vector3;
+    /// let sim = Mpu6886Sim::new();
+    /// // gyro wobbling around a bias of 0.01 rad/s
+    /// let delay = sim.sampling_delay(|sim, n| {
+    ///     let wobble = if n % 2 == 0 { 0.001 } else { -0.001 };
+    ///     sim.set_gyro(Vector3::repeat(0.01 + wobble));
+    /// });
+    /// ```
+    pub fn sampling_delay<F: FnMut(&Mpu6886Sim, u32)>(&self, stimulus: F) -> SamplingDelay<'_, F> {
+        SamplingDelay { sim: self, stimulus, n: 0 }
+    }
+
+    /// Reports wake on motion on the given axes in INT_STATUS
+    pub fn trigger_wom(&self, x: bool, y: bool, z: bool) {
+        let mut state = self.state.borrow_mut();
+        for (axis, bit) in [(x, INT_STATUS::WOM_X_INT), (y, INT_STATUS::WOM_Y_INT), (z, INT_STATUS::WOM_Z_INT)] {
+            if axis {
+                state.set_int_status(bit);
+            }
+        }
+    }
+
+    /// Register value, without read side effects
+    pub fn register(&self, reg: u8) -> u8 {
+        self.state.borrow().regs[reg as usize & 0x7f]
+    }
+
+    /// Overwrites register value, without write side effects and ignoring read-only
+    pub fn set_register(&self, reg: u8, value: u8) {
+        self.state.borrow_mut().regs[reg as usize & 0x7f] = value;
+    }
+
+    /// Number of bytes in the FIFO
+    pub fn fifo_len(&self) -> usize {
+        self.state.borrow().fifo_len
+    }
+}
+
+/// Delay of the simulator, returns immediately
+///
+/// Also the hal marker of [`SimInterface`], the driver expects it wherever a delay is needed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SimDelay;
+
+impl Delay<SimDelay> for SimDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+/// Delay taking a sample of the simulator after each wait, see `Mpu6886Sim::sampling_delay`
+pub struct SamplingDelay<'a, F> {
+    sim: &'a Mpu6886Sim,
+    stimulus: F,
+    n: u32,
+}
+
+impl<F: FnMut(&Mpu6886Sim, u32)> Delay<SimDelay> for SamplingDelay<'_, F> {
+    fn delay_ms(&mut self, _ms: u8) {
+        (self.stimulus)(self.sim, self.n);
+        self.n += 1;
+        self.sim.sample();
+    }
+}
+
+/// Register level access to the simulator
+pub struct SimInterface<'a> {
+    sim: &'a Mpu6886Sim,
+}
+
+impl Interface for SimInterface<'_> {
+    type Error = SimError;
+    type Hal = SimDelay;
+
+    fn write_register(&mut self, reg: u8, byte: u8) -> Result<(), SimError> {
+        self.sim.state.borrow_mut().write(reg, byte);
+        Ok(())
+    }
+
+    fn read_registers(&mut self, reg: u8, buf: &mut [u8]) -> Result<(), SimError> {
+        self.sim.state.borrow_mut().read_burst(reg, buf);
+        Ok(())
+    }
+}
+
+/// i2c bus to the simulator
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+pub struct SimI2c<'a> {
+    sim: &'a Mpu6886Sim,
+}
+
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+impl SimI2c<'_> {
+    fn check_addr(&self, addr: u8) -> Result<(), SimError> {
+        if addr == self.sim.state.borrow().slave_addr { Ok(()) } else { Err(SimError::Nack) }
+    }
+}
+
+/// spi bus to the simulator
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+pub struct SimSpi<'a> {
+    sim: &'a Mpu6886Sim,
+}
+
+#[cfg(any(feature = "eh0", feature = "eh1"))]
+impl SimSpi<'_> {
+    fn exchange(&mut self, out: u8) -> u8 {
+        self.sim.state.borrow_mut().spi_exchange(out)
+    }
+}
+
+/// chip select of the `embedded-hal` 0.2 spi bus to the simulator, ends transactions
+#[cfg(feature = "eh0")]
+pub struct SimCs<'a> {
+    sim: &'a Mpu6886Sim,
+}
+
+#[cfg(feature = "eh0")]
+mod eh0 {
+    use super::*;
+    use embedded_hal::blocking::{i2c, spi};
+    use embedded_hal::digital::v2::OutputPin;
+
+    impl i2c::Write for SimI2c<'_> {
+        type Error = SimError;
+
+        fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), SimError> {
+            self.check_addr(addr)?;
+            if let Some((reg, data)) = bytes.split_first() {
+                self.sim.state.borrow_mut().write_burst(*reg, data);
+            }
+            Ok(())
+        }
+    }
+
+    impl i2c::WriteRead for SimI2c<'_> {
+        type Error = SimError;
+
+        fn write_read(&mut self, addr: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), SimError> {
+            i2c::Write::write(self, addr, bytes)?;
+            self.sim.state.borrow_mut().read_burst(bytes.first().copied().unwrap_or(0), buffer);
+            Ok(())
+        }
+    }
+
+    impl spi::Write<u8> for SimSpi<'_> {
+        type Error = SimError;
+
+        fn write(&mut self, words: &[u8]) -> Result<(), SimError> {
+            for word in words {
+                self.exchange(*word);
+            }
+            Ok(())
+        }
+    }
+
+    impl spi::Transfer<u8> for SimSpi<'_> {
+        type Error = SimError;
+
+        fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], SimError> {
+            for word in words.iter_mut() {
+                *word = self.exchange(*word);
+            }
+            Ok(words)
+        }
+    }
+
+    impl OutputPin for SimCs<'_> {
+        type Error = core::convert::Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.sim.state.borrow_mut().spi = None;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.sim.state.borrow_mut().spi = None;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+mod eh1 {
+    use super::*;
+    use embedded_hal_1::{i2c, spi};
+
+    impl i2c::Error for SimError {
+        fn kind(&self) -> i2c::ErrorKind {
+            match self {
+                SimError::Nack => i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address),
+            }
+        }
+    }
+
+    impl spi::Error for SimError {
+        fn kind(&self) -> spi::ErrorKind {
+            spi::ErrorKind::Other
+        }
+    }
+
+    impl i2c::ErrorType for SimI2c<'_> {
+        type Error = SimError;
+    }
+
+    impl i2c::I2c for SimI2c<'_> {
+        fn transaction(&mut self, address: u8, operations: &mut [i2c::Operation<'_>]) -> Result<(), SimError> {
+            self.check_addr(address)?;
+            let mut state = self.sim.state.borrow_mut();
+            let mut reg = None;
+            for operation in operations {
+                match operation {
+                    i2c::Operation::Write(bytes) => {
+                        if let Some((first, data)) = bytes.split_first() {
+                            state.write_burst(*first, data);
+                            reg = Some(*first);
+                        }
+                    }
+                    i2c::Operation::Read(buf) => {
+                        state.read_burst(reg.unwrap_or(0), buf);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl spi::ErrorType for SimSpi<'_> {
+        type Error = SimError;
+    }
+
+    impl spi::SpiDevice for SimSpi<'_> {
+        fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), SimError> {
+            for operation in operations {
+                match operation {
+                    spi::Operation::Read(buf) => buf.iter_mut().for_each(|b| *b = self.exchange(0)),
+                    spi::Operation::Write(words) => words.iter().for_each(|w| { self.exchange(*w); }),
+                    spi::Operation::Transfer(read, write) => {
+                        for i in 0..read.len().max(write.len()) {
+                            let byte = self.exchange(write.get(i).copied().unwrap_or(0));
+                            if let Some(r) = read.get_mut(i) {
+                                *r = byte;
+                            }
+                        }
+                    }
+                    spi::Operation::TransferInPlace(words) => words.iter_mut().for_each(|w| *w = self.exchange(*w)),
+                    spi::Operation::DelayNs(_) => {}
+                }
+            }
+            self.sim.state.borrow_mut().spi = None;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(sim: &Mpu6886Sim, reg: u8) -> u8 {
+        let mut byte = [0];
+        sim.interface().read_registers(reg, &mut byte).unwrap();
+        byte[0]
+    }
+
+    #[test]
+    fn reset_values() {
+        let sim = Mpu6886Sim::new();
+        let mut iface = sim.interface();
+        assert_eq!(read(&sim, WHOAMI), 0x19);
+        assert_eq!(read(&sim, PWR_MGMT_1::ADDR), 0x41);
+        assert_eq!(read(&sim, CONFIG::ADDR), 0x80);
+
+        iface.write_register(GYRO_CONFIG::ADDR, 0x18).unwrap();
+        iface.write_register(PWR_MGMT_1::ADDR, 0x01).unwrap();
+        iface.write_register(PWR_MGMT_1::ADDR, 0x81).unwrap();
+        assert_eq!(read(&sim, PWR_MGMT_1::ADDR), 0x41);
+        assert_eq!(read(&sim, GYRO_CONFIG::ADDR), 0x00);
+    }
+
+    #[test]
+    fn read_only_registers() {
+        let sim = Mpu6886Sim::new();
+        let mut iface = sim.interface();
+        for reg in [WHOAMI, INT_STATUS::ADDR, ACC_REGX_H, GYRO_REGZ_H + 1, FIFO_COUNTH] {
+            iface.write_register(reg, 0x5a).unwrap();
+            assert_ne!(read(&sim, reg), 0x5a);
+        }
+    }
+
+    #[test]
+    fn int_status_cleared_on_read() {
+        let sim = Mpu6886Sim::new();
+        sim.trigger_wom(true, false, true);
+        assert_eq!(read(&sim, INT_PIN_CFG::ADDR), 0);
+        assert_eq!(read(&sim, INT_STATUS::ADDR), 0b1010_0000);
+        assert_eq!(read(&sim, INT_STATUS::ADDR), 0);
+
+        // INT_RD_CLEAR: any read clears
+        sim.interface().write_register(INT_PIN_CFG::ADDR, 1 << INT_PIN_CFG::INT_RD_CLEAR).unwrap();
+        sim.sample();
+        read(&sim, WHOAMI);
+        assert_eq!(read(&sim, INT_STATUS::ADDR), 0);
+    }
+
+    #[test]
+    fn burst_read_auto_increment() {
+        let sim = Mpu6886Sim::new();
+        sim.push_sample(Vector3::new(1.0, -1.0, 0.5), Vector3::zeros(), TEMP_OFFSET);
+        let mut buf = [0; 8];
+        sim.interface().read_registers(ACC_REGX_H, &mut buf).unwrap();
+        assert_eq!(buf, [0x40, 0x00, 0xc0, 0x00, 0x20, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn fifo() {
+        let sim = Mpu6886Sim::new();
+        let mut iface = sim.interface();
+        // not enabled yet
+        sim.sample();
+        assert_eq!(sim.fifo_len(), 0);
+
+        iface.write_register(FIFO_EN, 1 << 3).unwrap();
+        iface.write_register(USER_CTRL, 1 << 6).unwrap();
+        sim.push_sample(Vector3::new(1.0, 0.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        assert_eq!((sim.fifo_len(), read(&sim, FIFO_COUNTL)), (6, 6));
+
+        // FIFO_R_W is not incremented
+        let mut buf = [0; 7];
+        iface.read_registers(FIFO_R_W, &mut buf).unwrap();
+        assert_eq!(buf, [0x40, 0, 0, 0, 0, 0, 0xff]);
+
+        // accel, temp, gyro
+        iface.write_register(FIFO_EN, 0b11 << 3).unwrap();
+        sim.sample();
+        assert_eq!(sim.fifo_len(), 14);
+        iface.write_register(USER_CTRL, (1 << 6) | (1 << 2)).unwrap();
+        assert_eq!(sim.fifo_len(), 0);
+        assert_eq!(read(&sim, USER_CTRL), 1 << 6);
+    }
+
+    #[test]
+    fn fifo_overflow() {
+        let sim = Mpu6886Sim::new();
+        let mut iface = sim.interface();
+        iface.write_register(FIFO_EN, 1 << 3).unwrap();
+        iface.write_register(USER_CTRL, 1 << 6).unwrap();
+        for i in 0..FIFO_SIZE / 6 + 1 {
+            sim.push_sample(Vector3::new(i as f32 / 16384.0, 0.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        }
+        // oldest sample overwritten, 4 bytes of it left
+        assert_eq!(sim.fifo_len(), FIFO_SIZE);
+        assert_eq!(read(&sim, INT_STATUS::ADDR) & (1 << INT_STATUS::FIFO_OFLOW_INT), 1 << INT_STATUS::FIFO_OFLOW_INT);
+        let mut buf = [0; 6];
+        iface.read_registers(FIFO_R_W, &mut buf[..4]).unwrap();
+        iface.read_registers(FIFO_R_W, &mut buf).unwrap();
+        assert_eq!(buf[..2], [0, 1]);
+
+        // stop when full
+        iface.write_register(USER_CTRL, (1 << 6) | (1 << 2)).unwrap();
+        iface.write_register(CONFIG::ADDR, 1 << CONFIG::FIFO_MODE).unwrap();
+        for i in 0..FIFO_SIZE / 6 + 1 {
+            sim.push_sample(Vector3::new(i as f32 / 16384.0, 0.0, 0.0), Vector3::zeros(), TEMP_OFFSET);
+        }
+        // first 4 bytes of the last sample written
+        assert_eq!(sim.fifo_len(), FIFO_SIZE);
+        iface.read_registers(FIFO_R_W, &mut buf).unwrap();
+        assert_eq!(buf[..2], [0, 0]);
+    }
+
+    #[cfg(feature = "eh0")]
+    #[test]
+    fn eh0_buses() {
+        use embedded_hal::blocking::{i2c::{Write, WriteRead}, spi::{Transfer, Write as SpiWrite}};
+        use embedded_hal::digital::v2::OutputPin;
+
+        let sim = Mpu6886Sim::new();
+        let mut i2c = sim.i2c();
+        let mut buf = [0; 2];
+        assert_eq!(i2c.write_read(0x69, &[WHOAMI], &mut buf), Err(SimError::Nack));
+        i2c.write(DEFAULT_SLAVE_ADDR, &[GYRO_CONFIG::ADDR, 0x18, 0x08]).unwrap();
+        i2c.write_read(DEFAULT_SLAVE_ADDR, &[GYRO_CONFIG::ADDR], &mut buf).unwrap();
+        assert_eq!(buf, [0x18, 0x08]);
+
+        let (mut spi, mut cs) = (sim.spi(), sim.cs());
+        cs.set_low().unwrap();
+        SpiWrite::write(&mut spi, &[WHOAMI | 0x80]).unwrap();
+        let mut byte = [0];
+        spi.transfer(&mut byte).unwrap();
+        cs.set_high().unwrap();
+        assert_eq!(byte, [0x19]);
+    }
+
+    #[cfg(feature = "eh1")]
+    #[test]
+    fn eh1_buses() {
+        use embedded_hal_1::i2c::I2c;
+        use embedded_hal_1::spi::SpiDevice;
+
+        let sim = Mpu6886Sim::new_with_addr(0x69);
+        let mut i2c = sim.i2c();
+        let mut buf = [0; 2];
+        assert_eq!(i2c.write_read(DEFAULT_SLAVE_ADDR, &[WHOAMI], &mut buf), Err(SimError::Nack));
+        i2c.write(0x69, &[GYRO_CONFIG::ADDR, 0x18, 0x08]).unwrap();
+        i2c.write_read(0x69, &[GYRO_CONFIG::ADDR], &mut buf).unwrap();
+        assert_eq!(buf, [0x18, 0x08]);
+
+        let mut spi = sim.spi();
+        spi.write(&[ACCEL_CONFIG::ADDR, 0x10]).unwrap();
+        let mut buf = [0; 3];
+        spi.transfer(&mut buf, &[GYRO_CONFIG::ADDR | 0x80]).unwrap();
+        assert_eq!(buf, [0, 0x18, 0x10]);
+    }
+}